@@ -1,307 +1,830 @@
 #![doc = include_str!("./lib.md")]
 
-use attribute_derive::{Attribute, FromAttr};
+use attribute_derive::FromAttr;
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{format_ident, quote};
 
 #[derive(FromAttr, Default, Debug)]
 #[attribute(ident = get_size)]
 struct StructFieldAttribute {
-    #[attribute(conflicts = [size_fn, ignore])]
+    #[attribute(conflicts = [size_fn, size_fn_with_tracker, ignore])]
     size: Option<usize>,
-    #[attribute(conflicts = [size, ignore])]
-    size_fn: Option<syn::Ident>,
-    #[attribute(conflicts = [size, size_fn])]
+    #[attribute(conflicts = [size, size_fn_with_tracker, ignore])]
+    size_fn: Option<syn::Expr>,
+    #[attribute(conflicts = [size, size_fn, ignore])]
+    size_fn_with_tracker: Option<syn::Expr>,
+    #[attribute(conflicts = [size, size_fn, size_fn_with_tracker])]
     ignore: bool,
+    bound: Option<syn::LitStr>,
 }
 
-fn extract_ignored_generics_list(list: &Vec<syn::Attribute>) -> Vec<syn::PathSegment> {
-    let mut collection = Vec::new();
+impl StructFieldAttribute {
+    // Whether the field is measured using its own `GetSize` implementation.
+    const fn is_measured(&self) -> bool {
+        !self.ignore
+            && self.size.is_none()
+            && self.size_fn.is_none()
+            && self.size_fn_with_tracker.is_none()
+    }
+}
+
+#[derive(Default, Debug)]
+struct ContainerAttribute {
+    ignore: Vec<syn::Ident>,
+    heap_size_fn: Option<syn::Expr>,
+    with: Option<syn::Path>,
+    krate: Option<syn::Path>,
+    bound: Option<Vec<syn::WherePredicate>>,
+    remote: Option<syn::Path>,
+}
+
+impl ContainerAttribute {
+    // Parsed by hand, as `crate` can not be used as a field name with `FromAttr`.
+    fn from_attributes(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut result = Self::default();
+
+        for attr in attrs {
+            // Skip all attributes which do not belong to us.
+            if !attr.path().is_ident("get_size") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("ignore") {
+                    // #[get_size(ignore(A, B))]
+                    meta.parse_nested_meta(|meta| {
+                        result.ignore.push(meta.path.require_ident()?.clone());
+                        Ok(())
+                    })
+                } else if meta.path.is_ident("heap_size_fn") {
+                    if result.with.is_some() {
+                        return Err(
+                            meta.error("`heap_size_fn` conflicts with mutually exclusive `with`")
+                        );
+                    }
+                    set_once(
+                        &mut result.heap_size_fn,
+                        "heap_size_fn",
+                        &meta,
+                        meta.value()?.parse()?,
+                    )
+                } else if meta.path.is_ident("with") {
+                    if result.heap_size_fn.is_some() {
+                        return Err(
+                            meta.error("`with` conflicts with mutually exclusive `heap_size_fn`")
+                        );
+                    }
+                    set_once(&mut result.with, "with", &meta, meta.value()?.parse()?)
+                } else if meta.path.is_ident("bound") {
+                    let bound: syn::LitStr = meta.value()?.parse()?;
+                    set_once(&mut result.bound, "bound", &meta, parse_bound(&bound)?)
+                } else if meta.path.is_ident("remote") {
+                    let remote: syn::LitStr = meta.value()?.parse()?;
+                    set_once(&mut result.remote, "remote", &meta, remote.parse()?)
+                } else if meta.path.is_ident("crate") {
+                    let path: syn::LitStr = meta.value()?.parse()?;
+                    set_once(&mut result.krate, "crate", &meta, path.parse()?)
+                } else {
+                    Err(meta
+                        .error("supported fields are `ignore`, `heap_size_fn`, `with`, `bound`, `remote` and `crate`"))
+                }
+            })?;
+        }
 
-    for attr in list {
-        let mut list = extract_ignored_generics(attr);
+        if let Some(remote) = &result.remote
+            && (result.heap_size_fn.is_some() || result.with.is_some())
+        {
+            return Err(syn::Error::new_spanned(
+                remote,
+                "`remote` conflicts with `heap_size_fn` and `with`",
+            ));
+        }
 
-        collection.append(&mut list);
+        Ok(result)
     }
 
-    collection
+    // The path of the `get_size2` crate, used as the root of every emitted path.
+    fn krate(&self) -> syn::Path {
+        self.krate
+            .clone()
+            .unwrap_or_else(|| syn::parse_quote!(::get_size2))
+    }
 }
 
-fn extract_ignored_generics(attr: &syn::Attribute) -> Vec<syn::PathSegment> {
-    let mut collection = Vec::new();
-
-    // Skip all attributes which do not belong to us.
-    if !attr.meta.path().is_ident("get_size") {
-        return collection;
+fn set_once<T>(
+    slot: &mut Option<T>,
+    name: &str,
+    meta: &syn::meta::ParseNestedMeta<'_>,
+    value: T,
+) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(meta.error(format!("`{name}` is specified multiple times")));
     }
 
-    // Make sure it is a list.
-    let Ok(list) = attr.meta.require_list() else {
-        return collection;
-    };
+    *slot = Some(value);
+    Ok(())
+}
 
-    // Parse the nested meta.
-    // #[get_size(ignore(A, B))]
-    list.parse_nested_meta(|meta| {
-        // We only parse the ignore attributes.
-        if !meta.path.is_ident("ignore") {
-            return Ok(()); // Just skip.
-        }
+// Parse the where predicates given in a `bound = "..."` attribute.
+fn parse_bound(bound: &syn::LitStr) -> syn::Result<Vec<syn::WherePredicate>> {
+    let predicates = bound.parse_with(
+        syn::punctuated::Punctuated::<syn::WherePredicate, syn::Token![,]>::parse_terminated,
+    )?;
 
-        meta.parse_nested_meta(|meta| {
-            for segment in meta.path.segments {
-                collection.push(segment);
-            }
+    Ok(predicates.into_iter().collect())
+}
 
-            Ok(())
-        })?;
+// Collects the type parameters mentioned by a type, and whether it refers to the deriving type.
+struct TypeParamVisitor<'a> {
+    params: &'a [&'a syn::Ident],
+    name: &'a syn::Ident,
+    used: Vec<&'a syn::Ident>,
+    recursive: bool,
+}
 
-        Ok(())
-    })
-    .expect("Could not parse the ignore list.");
+impl<'ast> syn::visit::Visit<'ast> for TypeParamVisitor<'_> {
+    fn visit_path(&mut self, path: &'ast syn::Path) {
+        if let Some(first) = path.segments.first()
+            && let Some(param) = self.params.iter().find(|param| **param == &first.ident)
+            && !self.used.contains(param)
+        {
+            self.used.push(param);
+        }
 
-    collection
+        if path
+            .segments
+            .iter()
+            .any(|segment| segment.ident == *self.name || segment.ident == "Self")
+        {
+            self.recursive = true;
+        }
+
+        syn::visit::visit_path(self, path);
+    }
 }
 
-// Add a bound `T: GetSize` to every type parameter T, unless we ignore it.
-fn add_trait_bounds(mut generics: syn::Generics, ignored: &Vec<syn::PathSegment>) -> syn::Generics {
-    for param in &mut generics.params {
-        if let syn::GenericParam::Type(type_param) = param {
-            let mut found = false;
-            for ignored in ignored {
-                if ignored.ident == type_param.ident {
-                    found = true;
-                    break;
-                }
-            }
+// Add the where predicates required by the generated implementation.
+//
+// Every measured field whose type mentions a type parameter which is not ignored is bounded by
+// `FieldType: GetSize`. Recursive field types would make the trait solver go in circles, so these
+// bound the mentioned type parameters instead. Explicit `bound` attributes replace the inferred
+// predicates of the container or the field they are placed on.
+fn add_trait_bounds(
+    mut generics: syn::Generics,
+    data: &syn::Data,
+    container_attr: &ContainerAttribute,
+    name: &syn::Ident,
+    krate: &syn::Path,
+) -> syn::Result<syn::Generics> {
+    let params: Vec<_> = generics
+        .type_params()
+        .map(|param| &param.ident)
+        .filter(|ident| !container_attr.ignore.contains(ident))
+        .collect();
+
+    // The fields are not looked at when the whole measurement is delegated.
+    let fields: Vec<&syn::Field> = match data {
+        _ if container_attr.heap_size_fn.is_some() || container_attr.with.is_some() => Vec::new(),
+        syn::Data::Struct(data_struct) => data_struct.fields.iter().collect(),
+        syn::Data::Enum(data_enum) => data_enum
+            .variants
+            .iter()
+            .flat_map(|variant| &variant.fields)
+            .collect(),
+        syn::Data::Union(_) => Vec::new(),
+    };
 
-            if found {
-                continue;
+    let mut predicates: Vec<syn::WherePredicate> = Vec::new();
+
+    for field in fields {
+        let attr = StructFieldAttribute::from_attributes(&field.attrs)?;
+
+        if let Some(bound) = &attr.bound {
+            predicates.extend(parse_bound(bound)?);
+            continue;
+        }
+
+        if container_attr.bound.is_some() || !attr.is_measured() {
+            continue;
+        }
+
+        let mut visitor = TypeParamVisitor {
+            params: &params,
+            name,
+            used: Vec::new(),
+            recursive: false,
+        };
+        syn::visit::Visit::visit_type(&mut visitor, &field.ty);
+
+        if visitor.recursive {
+            for param in visitor.used {
+                predicates.push(syn::parse_quote!(#param: #krate::GetSize));
             }
+        } else if !visitor.used.is_empty() {
+            let field_ty = &field.ty;
+            predicates.push(syn::parse_quote!(#field_ty: #krate::GetSize));
+        }
+    }
+
+    if let Some(bound) = &container_attr.bound {
+        predicates.extend(bound.iter().cloned());
+    }
 
-            type_param
-                .bounds
-                .push(syn::parse_quote!(::get_size2::GetSize));
+    let where_clause = generics.make_where_clause();
+    for predicate in predicates {
+        if !where_clause.predicates.iter().any(|p| *p == predicate) {
+            where_clause.predicates.push(predicate);
         }
     }
-    generics
+
+    Ok(generics)
 }
 
-#[expect(
-    clippy::too_many_lines,
-    clippy::missing_panics_doc,
-    reason = "Needs refactoring"
-)]
 #[proc_macro_derive(GetSize, attributes(get_size))]
 pub fn derive_get_size(input: TokenStream) -> TokenStream {
     // Construct a representation of Rust code as a syntax tree
     // that we can manipulate
-    let ast: syn::DeriveInput = syn::parse(input).expect("Could not parse tokens");
+    let ast = syn::parse_macro_input!(input as syn::DeriveInput);
 
-    // The name of the sruct.
-    let name = &ast.ident;
+    expand(ast)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-    // Extract all generics we shall ignore.
-    let ignored = extract_ignored_generics_list(&ast.attrs);
+// Generate the code measuring a single field, returning the commands adding it to the heap size,
+// to the report named `parent` and to the heap size while failing on errors. The field is
+// reachable through the reference `value`.
+fn generate_field_cmds(
+    attr: &StructFieldAttribute,
+    field: &syn::Field,
+    field_name: &str,
+    value: &proc_macro2::TokenStream,
+    parent: &syn::Ident,
+    krate: &syn::Path,
+) -> (
+    proc_macro2::TokenStream,
+    proc_macro2::TokenStream,
+    proc_macro2::TokenStream,
+) {
+    let field_ty = &field.ty;
+
+    let try_add = quote! {
+        total = total
+            .checked_add(total_add)
+            .ok_or_else(#krate::GetSizeError::overflow::<Self>)?;
+    };
+
+    if let Some(size) = attr.size {
+        (
+            quote! {
+                total += #size;
+            },
+            quote! {
+                #parent.add_inline_child(#krate::SizeReport::new(#field_name, ::std::mem::size_of::<#field_ty>(), #size));
+            },
+            quote! {
+                let total_add = #size;
+                #try_add
+            },
+        )
+    } else if let Some(size_fn) = &attr.size_fn {
+        (
+            quote! {
+                total += (#size_fn)(#value);
+            },
+            quote! {
+                #parent.add_inline_child(#krate::SizeReport::new(#field_name, ::std::mem::size_of::<#field_ty>(), (#size_fn)(#value)));
+            },
+            quote! {
+                let total_add = (#size_fn)(#value);
+                #try_add
+            },
+        )
+    } else if let Some(size_fn) = &attr.size_fn_with_tracker {
+        (
+            quote! {
+                let (total_add, tracker) = (#size_fn)(#value, tracker);
+                total += total_add;
+            },
+            quote! {
+                let (heap_size, tracker) = (#size_fn)(#value, tracker);
+                #parent.add_inline_child(#krate::SizeReport::new(#field_name, ::std::mem::size_of::<#field_ty>(), heap_size));
+            },
+            quote! {
+                let (total_add, tracker) = (#size_fn)(#value, tracker);
+                #try_add
+            },
+        )
+    } else {
+        (
+            quote! {
+                let (total_add, tracker) = #krate::GetSize::get_heap_size_with_tracker(#value, tracker);
+                total += total_add;
+            },
+            quote! {
+                let (child, tracker) = #krate::GetSize::get_size_report_with_tracker(#value, tracker);
+                #parent.add_inline_child(child.named(#field_name));
+            },
+            quote! {
+                let (total_add, tracker) = #krate::GetSize::try_get_heap_size_with_tracker(#value, tracker)?;
+                #try_add
+            },
+        )
+    }
+}
 
-    // Add a bound `T: GetSize` to every type parameter T.
-    let generics = add_trait_bounds(ast.generics, &ignored);
+// The pattern destructuring some fields, together with the commands measuring them.
+struct FieldCmds {
+    pattern: proc_macro2::TokenStream,
+    cmds: Vec<proc_macro2::TokenStream>,
+    report_cmds: Vec<proc_macro2::TokenStream>,
+    try_cmds: Vec<proc_macro2::TokenStream>,
+}
 
-    // Extract the generics of the struct/enum.
-    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+// Generate the pattern destructuring the given fields, together with the commands measuring them.
+//
+// The pattern lists every field without a rest pattern, which makes sure that the fields of
+// remote types mirror the real field list.
+fn generate_fields(
+    fields: &syn::Fields,
+    parent: &syn::Ident,
+    krate: &syn::Path,
+) -> syn::Result<FieldCmds> {
+    let mut patterns = Vec::with_capacity(fields.len());
+    let mut cmds = Vec::with_capacity(fields.len());
+    let mut report_cmds = Vec::with_capacity(fields.len());
+    let mut try_cmds = Vec::with_capacity(fields.len());
+
+    for (i, field) in fields.iter().enumerate() {
+        // Parse all relevant attributes.
+        let attr = StructFieldAttribute::from_attributes(&field.attrs)?;
+
+        let field_name = field
+            .ident
+            .as_ref()
+            .map_or_else(|| i.to_string(), ToString::to_string);
+
+        let member = field.ident.clone().map_or_else(
+            || syn::Member::Unnamed(syn::Index::from(i)),
+            syn::Member::Named,
+        );
+
+        // Fields which are not measured are not bound, to avoid unused variables.
+        let binding = if attr.ignore || attr.size.is_some() {
+            quote!(_)
+        } else {
+            let binding = format_ident!("v{}", i);
+            quote!(#binding)
+        };
+
+        if !attr.ignore {
+            let (cmd, report_cmd, try_cmd) =
+                generate_field_cmds(&attr, field, &field_name, &binding, parent, krate);
+            cmds.push(cmd);
+            report_cmds.push(report_cmd);
+            try_cmds.push(try_cmd);
+        }
 
-    // Traverse the parsed data to generate the individual parts of the function.
-    match ast.data {
-        syn::Data::Enum(data_enum) => {
-            if data_enum.variants.is_empty() {
-                // Empty enums are easy to implement.
-                let generated = quote! {
-                    impl ::get_size2::GetSize for #name {}
-                };
-                return generated.into();
-            }
+        patterns.push(quote!(#member: #binding));
+    }
 
+    Ok(FieldCmds {
+        pattern: quote!({ #(#patterns,)* }),
+        cmds,
+        report_cmds,
+        try_cmds,
+    })
+}
+
+// Generate the bodies of `get_heap_size_with_tracker`, `get_size_report_with_tracker` and
+// `try_get_heap_size_with_tracker`, measuring the reference `value` to a value of type `path`,
+// which occupies `stack_size` bytes.
+fn generate_bodies(
+    data: &syn::Data,
+    value: &proc_macro2::TokenStream,
+    path: &proc_macro2::TokenStream,
+    stack_size: &proc_macro2::TokenStream,
+    krate: &syn::Path,
+) -> syn::Result<(
+    proc_macro2::TokenStream,
+    proc_macro2::TokenStream,
+    proc_macro2::TokenStream,
+)> {
+    match data {
+        syn::Data::Enum(data_enum) => {
             let mut cmds = Vec::with_capacity(data_enum.variants.len());
+            let mut report_cmds = Vec::with_capacity(data_enum.variants.len());
+            let mut try_cmds = Vec::with_capacity(data_enum.variants.len());
 
-            for variant in data_enum.variants {
-                let ident = &variant.ident;
+            let variant_report = format_ident!("variant");
 
-                match &variant.fields {
-                    syn::Fields::Unnamed(unnamed_fields) => {
-                        let num_fields = unnamed_fields.unnamed.len();
+            for variant in &data_enum.variants {
+                let ident = &variant.ident;
+                let variant_name = ident.to_string();
 
-                        let mut field_idents = Vec::with_capacity(num_fields);
-                        for i in 0..num_fields {
-                            let field_ident = String::from("v") + &i.to_string();
-                            let field_ident = syn::parse_str::<syn::Ident>(&field_ident)
-                                .expect("Could not parse string to ident.");
+                let FieldCmds {
+                    pattern,
+                    cmds: field_cmds,
+                    report_cmds: field_report_cmds,
+                    try_cmds: field_try_cmds,
+                } = generate_fields(&variant.fields, &variant_report, krate)?;
 
-                            field_idents.push(field_ident);
-                        }
+                cmds.push(quote! {
+                    #path::#ident #pattern => {
+                        let mut total = 0;
 
-                        let mut field_cmds = Vec::with_capacity(num_fields);
+                        #(#field_cmds)*;
 
-                        for (i, _field) in unnamed_fields.unnamed.iter().enumerate() {
-                            let field_ident = String::from("v") + &i.to_string();
-                            let field_ident = syn::parse_str::<syn::Ident>(&field_ident)
-                                .expect("Could not parse string to ident.");
+                        (total, tracker)
+                    }
+                });
 
-                            field_cmds.push(quote! {
-                                let (total_add, tracker) = ::get_size2::GetSize::get_heap_size_with_tracker(#field_ident, tracker);
-                                total += total_add;
-                            });
-                        }
+                report_cmds.push(quote! {
+                    #path::#ident #pattern => {
+                        let mut #variant_report = #krate::SizeReport::new(#variant_name, stack_size, 0);
 
-                        cmds.push(quote! {
-                            Self::#ident(#(#field_idents,)*) => {
-                                let mut total = 0;
+                        #(#field_report_cmds)*;
 
-                                #(#field_cmds)*;
+                        report.add_inline_child(#variant_report);
 
-                                (total, tracker)
-                            }
-                        });
+                        (report, tracker)
                     }
-                    syn::Fields::Named(named_fields) => {
-                        let num_fields = named_fields.named.len();
+                });
 
-                        let mut field_idents = Vec::with_capacity(num_fields);
+                try_cmds.push(quote! {
+                    #path::#ident #pattern => {
+                        let mut total: usize = 0;
 
-                        let mut field_cmds = Vec::with_capacity(num_fields);
+                        #(#field_try_cmds)*;
 
-                        for field in &named_fields.named {
-                            let field_ident =
-                                field.ident.as_ref().expect("Could not get field ident.");
+                        ::core::result::Result::Ok((total, tracker))
+                    }
+                });
+            }
 
-                            field_idents.push(field_ident);
+            Ok((
+                quote! {
+                    match #value {
+                        #(#cmds)*
+                    }
+                },
+                quote! {
+                    let stack_size = #stack_size;
+                    let mut report = #krate::SizeReport::new("", stack_size, 0);
 
-                            field_cmds.push(quote! {
-                                let (total_add, tracker) = ::get_size2::GetSize::get_heap_size_with_tracker(#field_ident, tracker);
-                                total += total_add;
-                            });
-                        }
+                    match #value {
+                        #(#report_cmds)*
+                    }
+                },
+                quote! {
+                    match #value {
+                        #(#try_cmds)*
+                    }
+                },
+            ))
+        }
+        syn::Data::Union(data_union) => Err(syn::Error::new(
+            data_union.union_token.span,
+            "deriving `GetSize` for unions requires `#[get_size(heap_size_fn = ...)]` or `#[get_size(with = ...)]`",
+        )),
+        syn::Data::Struct(data_struct) => {
+            let report = format_ident!("report");
 
-                        cmds.push(quote! {
-                            Self::#ident{#(#field_idents,)*} => {
-                                let mut total = 0;
+            let FieldCmds {
+                pattern,
+                cmds,
+                report_cmds,
+                try_cmds,
+            } = generate_fields(&data_struct.fields, &report, krate)?;
 
-                                #(#field_cmds)*;
+            Ok((
+                quote! {
+                    let #path #pattern = #value;
+                    let mut total = 0;
 
-                                (total, tracker)
-                            }
-                        });
-                    }
-                    syn::Fields::Unit => {
-                        cmds.push(quote! {
-                            Self::#ident => (0, tracker),
-                        });
-                    }
-                }
-            }
+                    #(#cmds)*;
 
-            // Build the trait implementation
-            let generated = quote! {
-                impl #impl_generics ::get_size2::GetSize for #name #ty_generics #where_clause {
-                    fn get_heap_size(&self) -> usize {
-                        let tracker = get_size2::StandardTracker::default();
+                    (total, tracker)
+                },
+                quote! {
+                    let mut #report = #krate::SizeReport::new("", #stack_size, 0);
+                    let #path #pattern = #value;
 
-                        let (total, _) = ::get_size2::GetSize::get_heap_size_with_tracker(self, tracker);
+                    #(#report_cmds)*;
 
-                        total
-                    }
+                    (#report, tracker)
+                },
+                quote! {
+                    let #path #pattern = #value;
+                    let mut total: usize = 0;
 
-                    fn get_heap_size_with_tracker<TRACKER: ::get_size2::GetSizeTracker>(
-                        &self,
-                        tracker: TRACKER,
-                    ) -> (usize, TRACKER) {
-                        match self {
-                            #(#cmds)*
-                        }
-                    }
-                }
-            };
-            generated.into()
+                    #(#try_cmds)*;
+
+                    ::core::result::Result::Ok((total, tracker))
+                },
+            ))
         }
-        syn::Data::Union(_data_union) => {
-            panic!("Deriving GetSize for unions is currently not supported.")
+    }
+}
+
+// Generate the conversions between a remote type and its mirror, moving every field across.
+//
+// Besides being useful on their own, these make sure that the fields of the mirror match the
+// fields of the remote type, and keep the mirror from being reported as dead code.
+fn generate_remote_conversions(
+    data: &syn::Data,
+    name: &syn::Ident,
+    remote: &syn::Path,
+    generics: &syn::Generics,
+) -> proc_macro2::TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let fields_pattern = |fields: &syn::Fields| {
+        let fields = fields.iter().enumerate().map(|(i, field)| {
+            let member = field.ident.clone().map_or_else(
+                || syn::Member::Unnamed(syn::Index::from(i)),
+                syn::Member::Named,
+            );
+            let binding = format_ident!("v{}", i);
+
+            quote!(#member: #binding)
+        });
+
+        quote!({ #(#fields,)* })
+    };
+
+    let (into_mirror, into_remote) = match data {
+        syn::Data::Enum(data_enum) => {
+            let (into_mirror, into_remote): (Vec<_>, Vec<_>) = data_enum
+                .variants
+                .iter()
+                .map(|variant| {
+                    let ident = &variant.ident;
+                    let pattern = fields_pattern(&variant.fields);
+
+                    (
+                        quote!(#remote::#ident #pattern => Self::#ident #pattern,),
+                        quote!(#name::#ident #pattern => Self::#ident #pattern,),
+                    )
+                })
+                .unzip();
+
+            (
+                quote!(match value { #(#into_mirror)* }),
+                quote!(match value { #(#into_remote)* }),
+            )
         }
         syn::Data::Struct(data_struct) => {
-            if data_struct.fields.is_empty() {
-                // Empty structs are easy to implement.
-                let generated = quote! {
-                    impl ::get_size2::GetSize for #name {}
-                };
-                return generated.into();
+            let pattern = fields_pattern(&data_struct.fields);
+
+            (
+                quote! {
+                    let #remote #pattern = value;
+                    Self #pattern
+                },
+                quote! {
+                    let #name #pattern = value;
+                    Self #pattern
+                },
+            )
+        }
+        // Unions are rejected before.
+        syn::Data::Union(_) => return quote!(),
+    };
+
+    quote! {
+        impl #impl_generics ::core::convert::From<#remote #ty_generics> for #name #ty_generics #where_clause {
+            fn from(value: #remote #ty_generics) -> Self {
+                #into_mirror
             }
+        }
 
-            let mut cmds = Vec::with_capacity(data_struct.fields.len());
+        impl #impl_generics ::core::convert::From<#name #ty_generics> for #remote #ty_generics #where_clause {
+            fn from(value: #name #ty_generics) -> Self {
+                #into_remote
+            }
+        }
+    }
+}
+
+// Reject all field attributes of a type whose measurement is delegated by the container attribute
+// `delegate`, since they would be ignored. Misspelled attributes are reported as such.
+fn reject_field_attributes(data: &syn::Data, delegate: &str) -> syn::Result<()> {
+    let fields: Vec<&syn::Field> = match data {
+        syn::Data::Enum(data_enum) => data_enum
+            .variants
+            .iter()
+            .flat_map(|variant| &variant.fields)
+            .collect(),
+        syn::Data::Struct(data_struct) => data_struct.fields.iter().collect(),
+        syn::Data::Union(data_union) => data_union.fields.named.iter().collect(),
+    };
+
+    for field in fields {
+        StructFieldAttribute::from_attributes(&field.attrs)?;
+
+        if let Some(attr) = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("get_size"))
+        {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!(
+                    "field attributes have no effect, since `{delegate}` measures the whole value"
+                ),
+            ));
+        }
+    }
 
-            let mut unidentified_fields_count = 0; // For newtypes
+    Ok(())
+}
 
-            for field in &data_struct.fields {
-                // Parse all relevant attributes.
-                let attr = StructFieldAttribute::from_attributes(&field.attrs)
-                    .expect("Could not parse attributes.");
+fn expand(ast: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    // The name of the sruct.
+    let name = &ast.ident;
 
-                // NOTE There will be no attributes if this is a tuple struct.
-                if let Some(size) = attr.size {
-                    cmds.push(quote! {
-                        total += #size;
-                    });
+    // Parse the container attributes, including all generics we shall ignore.
+    let container_attr = ContainerAttribute::from_attributes(&ast.attrs)?;
 
-                    continue;
-                } else if let Some(size_fn) = attr.size_fn {
-                    let ident = field.ident.as_ref().expect("Could not get field ident.");
+    let krate = container_attr.krate();
 
-                    cmds.push(quote! {
-                        total += #size_fn(&self.#ident);
-                    });
+    // Add the bounds required by the measured fields.
+    let generics = add_trait_bounds(ast.generics, &ast.data, &container_attr, name, &krate)?;
 
-                    continue;
-                } else if attr.ignore {
-                    continue;
+    // Extract the generics of the struct/enum.
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // The whole measurement may be delegated, which does not require to look at the data at all.
+    if let Some(heap_size_fn) = &container_attr.heap_size_fn {
+        reject_field_attributes(&ast.data, "heap_size_fn")?;
+
+        return Ok(quote! {
+            impl #impl_generics #krate::GetSize for #name #ty_generics #where_clause {
+                fn get_heap_size(&self) -> usize {
+                    (#heap_size_fn)(self)
                 }
+            }
+        });
+    }
 
-                if let Some(ident) = field.ident.as_ref() {
-                    cmds.push(quote! {
-                        let (total_add, tracker) = ::get_size2::GetSize::get_heap_size_with_tracker(&self.#ident, tracker);
-                        total += total_add;
-                    });
-                } else {
-                    let current_index = syn::Index::from(unidentified_fields_count);
-                    cmds.push(quote! {
-                        let (total_add, tracker) = ::get_size2::GetSize::get_heap_size_with_tracker(&self.#current_index, tracker);
-                        total += total_add;
-                    });
+    if let Some(with) = &container_attr.with {
+        reject_field_attributes(&ast.data, "with")?;
+
+        return Ok(quote! {
+            impl #impl_generics #krate::GetSize for #name #ty_generics #where_clause {
+                fn get_heap_size(&self) -> usize {
+                    let tracker = #krate::StandardTracker::default();
+
+                    let (total, _) = #with::get_heap_size_with_tracker(self, tracker);
 
-                    unidentified_fields_count += 1;
+                    total
+                }
+
+                fn get_heap_size_with_tracker<__GetSizeTracker: #krate::GetSizeTracker>(
+                    &self,
+                    tracker: __GetSizeTracker,
+                ) -> (usize, __GetSizeTracker) {
+                    #with::get_heap_size_with_tracker(self, tracker)
                 }
             }
+        });
+    }
 
-            // Build the trait implementation
-            let generated = quote! {
-                impl #impl_generics ::get_size2::GetSize for #name #ty_generics #where_clause {
-                    fn get_heap_size(&self) -> usize {
-                        let tracker = get_size2::StandardTracker::default();
+    // A remote definition mirrors a foreign type, which is measured by associated functions.
+    if let Some(remote) = &container_attr.remote {
+        let vis = &ast.vis;
+        let remote_ty = quote!(#remote #ty_generics);
 
-                        let (total, _) = ::get_size2::GetSize::get_heap_size_with_tracker(self, tracker);
+        let (heap_body, report_body, try_body) = generate_bodies(
+            &ast.data,
+            &quote!(value),
+            &quote!(#remote),
+            &quote!(::core::mem::size_of::<#remote_ty>()),
+            &krate,
+        )?;
 
-                        total
-                    }
+        let conversions = generate_remote_conversions(&ast.data, name, remote, &generics);
 
-                    fn get_heap_size_with_tracker<TRACKER: ::get_size2::GetSizeTracker>(
-                        &self,
-                        tracker: TRACKER,
-                    ) -> (usize, TRACKER) {
-                        let mut total = 0;
+        return Ok(quote! {
+            #conversions
 
-                        #(#cmds)*;
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// Determines how many bytes the remote `value` occupies inside the heap.
+                #vis fn get_heap_size(value: &#remote_ty) -> usize {
+                    let tracker = #krate::StandardTracker::default();
 
-                        (total, tracker)
-                    }
+                    let (total, _) = Self::get_heap_size_with_tracker(value, tracker);
+
+                    total
                 }
-            };
-            generated.into()
-        }
+
+                /// Determines how many bytes the remote `value` occupies inside the heap while
+                /// using a `tracker`.
+                #vis fn get_heap_size_with_tracker<__GetSizeTracker: #krate::GetSizeTracker>(
+                    value: &#remote_ty,
+                    tracker: __GetSizeTracker,
+                ) -> (usize, __GetSizeTracker) {
+                    #heap_body
+                }
+
+                /// Creates a size report for the remote `value` while using a `tracker`.
+                #vis fn get_size_report_with_tracker<__GetSizeTracker: #krate::GetSizeTracker>(
+                    value: &#remote_ty,
+                    tracker: __GetSizeTracker,
+                ) -> (#krate::SizeReport, __GetSizeTracker) {
+                    #report_body
+                }
+
+                /// Determines how many bytes the remote `value` occupies inside the heap while
+                /// using a `tracker`, failing instead of skipping values which can not be
+                /// inspected.
+                #vis fn try_get_heap_size_with_tracker<__GetSizeTracker: #krate::GetSizeTracker>(
+                    value: &#remote_ty,
+                    tracker: __GetSizeTracker,
+                ) -> ::core::result::Result<(usize, __GetSizeTracker), #krate::GetSizeError> {
+                    #try_body
+                }
+            }
+
+            impl #impl_generics #krate::GetSizeRemote<#remote_ty> for #name #ty_generics #where_clause {
+                fn get_heap_size_with_tracker<__GetSizeTracker: #krate::GetSizeTracker>(
+                    value: &#remote_ty,
+                    tracker: __GetSizeTracker,
+                ) -> (usize, __GetSizeTracker) {
+                    Self::get_heap_size_with_tracker(value, tracker)
+                }
+
+                fn get_size_report_with_tracker<__GetSizeTracker: #krate::GetSizeTracker>(
+                    value: &#remote_ty,
+                    tracker: __GetSizeTracker,
+                ) -> (#krate::SizeReport, __GetSizeTracker) {
+                    Self::get_size_report_with_tracker(value, tracker)
+                }
+
+                fn try_get_heap_size_with_tracker<__GetSizeTracker: #krate::GetSizeTracker>(
+                    value: &#remote_ty,
+                    tracker: __GetSizeTracker,
+                ) -> ::core::result::Result<(usize, __GetSizeTracker), #krate::GetSizeError> {
+                    Self::try_get_heap_size_with_tracker(value, tracker)
+                }
+            }
+        });
+    }
+
+    // Empty types are easy to implement.
+    let is_empty = match &ast.data {
+        syn::Data::Enum(data_enum) => data_enum.variants.is_empty(),
+        syn::Data::Struct(data_struct) => data_struct.fields.is_empty(),
+        syn::Data::Union(_) => false,
+    };
+
+    if is_empty {
+        return Ok(quote! {
+            impl #impl_generics #krate::GetSize for #name #ty_generics #where_clause {}
+        });
     }
+
+    // Traverse the parsed data to generate the individual parts of the function.
+    let (heap_body, report_body, try_body) = generate_bodies(
+        &ast.data,
+        &quote!(self),
+        &quote!(Self),
+        &quote!(<Self as #krate::GetSize>::get_stack_size()),
+        &krate,
+    )?;
+
+    // Build the trait implementation
+    Ok(quote! {
+        impl #impl_generics #krate::GetSize for #name #ty_generics #where_clause {
+            fn get_heap_size(&self) -> usize {
+                let tracker = #krate::StandardTracker::default();
+
+                let (total, _) = #krate::GetSize::get_heap_size_with_tracker(self, tracker);
+
+                total
+            }
+
+            fn get_heap_size_with_tracker<__GetSizeTracker: #krate::GetSizeTracker>(
+                &self,
+                tracker: __GetSizeTracker,
+            ) -> (usize, __GetSizeTracker) {
+                #heap_body
+            }
+
+            fn get_size_report_with_tracker<__GetSizeTracker: #krate::GetSizeTracker>(
+                &self,
+                tracker: __GetSizeTracker,
+            ) -> (#krate::SizeReport, __GetSizeTracker) {
+                #report_body
+            }
+
+            fn try_get_heap_size_with_tracker<__GetSizeTracker: #krate::GetSizeTracker>(
+                &self,
+                tracker: __GetSizeTracker,
+            ) -> ::core::result::Result<(usize, __GetSizeTracker), #krate::GetSizeError> {
+                #try_body
+            }
+        }
+    })
 }
@@ -0,0 +1,15 @@
+use get_size2::GetSize;
+
+#[derive(GetSize)]
+#[get_size(heap_size_fn = heap_size, with = sizes)]
+struct Test {
+    value: String,
+}
+
+fn heap_size(value: &Test) -> usize {
+    value.value.len()
+}
+
+mod sizes {}
+
+fn main() {}
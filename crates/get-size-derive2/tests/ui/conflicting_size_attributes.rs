@@ -0,0 +1,13 @@
+use get_size2::GetSize;
+
+#[derive(GetSize)]
+struct Test {
+    #[get_size(size = 10, size_fn = custom_size)]
+    value: String,
+}
+
+fn custom_size(value: &String) -> usize {
+    value.len()
+}
+
+fn main() {}
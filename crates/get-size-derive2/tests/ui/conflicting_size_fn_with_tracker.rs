@@ -0,0 +1,13 @@
+use get_size2::{GetSize, GetSizeTracker};
+
+#[derive(GetSize)]
+struct Test {
+    #[get_size(size_fn = String::len, size_fn_with_tracker = custom_size)]
+    value: String,
+}
+
+fn custom_size<T: GetSizeTracker>(value: &String, tracker: T) -> (usize, T) {
+    (value.len(), tracker)
+}
+
+fn main() {}
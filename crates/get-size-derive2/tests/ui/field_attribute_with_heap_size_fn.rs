@@ -0,0 +1,14 @@
+use get_size2::GetSize;
+
+#[derive(GetSize)]
+#[get_size(heap_size_fn = heap_size)]
+struct Test {
+    #[get_size(ignore)]
+    value: String,
+}
+
+fn heap_size(value: &Test) -> usize {
+    value.value.len()
+}
+
+fn main() {}
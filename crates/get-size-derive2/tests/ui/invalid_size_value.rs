@@ -0,0 +1,9 @@
+use get_size2::GetSize;
+
+#[derive(GetSize)]
+struct Test {
+    #[get_size(size = "ten")]
+    value: String,
+}
+
+fn main() {}
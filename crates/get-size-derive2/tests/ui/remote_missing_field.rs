@@ -0,0 +1,16 @@
+use get_size2::GetSize;
+
+mod upstream {
+    pub struct Message {
+        pub id: u64,
+        pub body: String,
+    }
+}
+
+#[derive(GetSize)]
+#[get_size(remote = "upstream::Message")]
+struct MessageDef {
+    id: u64,
+}
+
+fn main() {}
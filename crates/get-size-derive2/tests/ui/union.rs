@@ -0,0 +1,9 @@
+use get_size2::GetSize;
+
+#[derive(GetSize)]
+union Test {
+    a: u32,
+    b: f32,
+}
+
+fn main() {}
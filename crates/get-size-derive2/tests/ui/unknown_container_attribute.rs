@@ -0,0 +1,9 @@
+use get_size2::GetSize;
+
+#[derive(GetSize)]
+#[get_size(skip(T))]
+struct Test<T> {
+    value: T,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use get_size2::GetSize;
+
+#[derive(GetSize)]
+#[get_size(with = sizes)]
+enum Test {
+    Value(#[get_size(skip)] String),
+}
+
+mod sizes {
+    use get_size2::GetSizeTracker;
+
+    pub fn get_heap_size_with_tracker<T: GetSizeTracker>(_value: &super::Test, tracker: T) -> (usize, T) {
+        (0, tracker)
+    }
+}
+
+fn main() {}
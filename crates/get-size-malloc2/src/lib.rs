@@ -0,0 +1,110 @@
+#![doc = include_str!("./lib.md")]
+
+use std::alloc::Layout;
+use std::ffi::c_void;
+
+use get_size2::{Allocation, AllocatorModel};
+
+#[cfg(test)]
+mod test;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[expect(
+    unsafe_code,
+    reason = "Declares the allocator functions of the C library"
+)]
+unsafe extern "C" {
+    fn malloc_usable_size(ptr: *mut c_void) -> usize;
+}
+
+/// An [`AllocatorModel`] which asks the C library for the usable size of every allocation by
+/// calling `malloc_usable_size`.
+///
+/// Both glibc and musl provide `malloc_usable_size`, so this model is available on Linux and
+/// Android. It requires the global allocator to be the [`System`](std::alloc::System) allocator,
+/// which is the default of Rust.
+///
+/// Only the buffers of [`Vec`] and [`String`] as well as [`Box`] are measured through their
+/// pointers. All other allocations, including the tables of the hash maps and the nodes of a
+/// [`BTreeMap`](std::collections::BTreeMap), fall back to the size of their layout.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Debug, Clone, Copy)]
+pub struct MallocUsableSize {
+    _private: (),
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl MallocUsableSize {
+    /// Creates a new model, which queries `malloc_usable_size` for the allocations.
+    ///
+    /// # Safety
+    ///
+    /// The global allocator of the program must be the [`System`](std::alloc::System) allocator,
+    /// since `malloc_usable_size` must only be called on pointers returned by `malloc`.
+    #[expect(unsafe_code, reason = "The caller must assert which allocator is used")]
+    #[must_use]
+    pub const unsafe fn new() -> Self {
+        Self { _private: () }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl AllocatorModel for MallocUsableSize {
+    fn allocation_size(&self, layout: Layout) -> usize {
+        layout.size()
+    }
+
+    #[expect(unsafe_code, reason = "Calls into the C library")]
+    fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+        // SAFETY: The constructor asserts that the global allocator is `malloc`, and the
+        // allocation is guaranteed to be a live allocation of the global allocator.
+        unsafe { malloc_usable_size(allocation.ptr().as_ptr().cast()) }
+    }
+}
+
+/// An [`AllocatorModel`] which asks [jemalloc](https://jemalloc.net/) for the usable size of
+/// every allocation by calling `sallocx`.
+///
+/// It requires the global allocator to be the `Jemalloc` allocator of the
+/// [`tikv-jemallocator`](https://crates.io/crates/tikv-jemallocator) crate, using the prefixed
+/// symbols of its default configuration.
+///
+/// Only the buffers of [`Vec`] and [`String`] as well as [`Box`] are measured through their
+/// pointers. All other allocations, including the tables of the hash maps and the nodes of a
+/// [`BTreeMap`](std::collections::BTreeMap), fall back to the size of their layout.
+#[cfg(feature = "jemalloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "jemalloc")))]
+#[derive(Debug, Clone, Copy)]
+pub struct JemallocUsableSize {
+    _private: (),
+}
+
+#[cfg(feature = "jemalloc")]
+impl JemallocUsableSize {
+    /// Creates a new model, which queries `sallocx` for the allocations.
+    ///
+    /// # Safety
+    ///
+    /// The global allocator of the program must be the `Jemalloc` allocator of the
+    /// `tikv-jemallocator` crate, since `sallocx` must only be called on pointers returned by
+    /// jemalloc.
+    #[expect(unsafe_code, reason = "The caller must assert which allocator is used")]
+    #[must_use]
+    pub const unsafe fn new() -> Self {
+        Self { _private: () }
+    }
+}
+
+#[cfg(feature = "jemalloc")]
+impl AllocatorModel for JemallocUsableSize {
+    fn allocation_size(&self, layout: Layout) -> usize {
+        layout.size()
+    }
+
+    #[expect(unsafe_code, reason = "Calls into jemalloc")]
+    fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+        // SAFETY: The constructor asserts that the global allocator is jemalloc, and the
+        // allocation is guaranteed to be a live allocation of the global allocator.
+        unsafe { tikv_jemalloc_sys::sallocx(allocation.ptr().as_ptr().cast_const().cast(), 0) }
+    }
+}
@@ -0,0 +1,78 @@
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use std::collections::HashMap;
+
+use get_size2::GetSize;
+
+use super::*;
+
+#[expect(unsafe_code, reason = "The tests use the default system allocator")]
+fn model() -> MallocUsableSize {
+    unsafe { MallocUsableSize::new() }
+}
+
+#[expect(unsafe_code, reason = "Queries the C library directly")]
+fn usable_size<T>(ptr: *const T) -> usize {
+    unsafe { malloc_usable_size(ptr.cast_mut().cast()) }
+}
+
+#[test]
+fn malloc_usable_size_of_buffers() {
+    let value: Vec<u8> = Vec::with_capacity(10);
+    let size = value.get_heap_size_with_model(model());
+    assert_eq!(size, usable_size(value.as_ptr()));
+    assert!(size >= 10, "The allocator must reserve the requested bytes");
+
+    let value = String::from("Hello World!");
+    assert_eq!(
+        value.get_heap_size_with_model(model()),
+        usable_size(value.as_ptr())
+    );
+
+    let value = Box::new([0u64; 5]);
+    assert_eq!(
+        value.get_heap_size_with_model(model()),
+        usable_size(&raw const *value)
+    );
+
+    // Nothing is allocated for empty buffers.
+    assert_eq!(Vec::<u64>::new().get_heap_size_with_model(model()), 0);
+    assert_eq!(String::new().get_heap_size_with_model(model()), 0);
+}
+
+#[test]
+fn malloc_usable_size_of_nested_buffers() {
+    #[derive(GetSize)]
+    struct Test {
+        name: String,
+        values: Box<[u32]>,
+    }
+
+    let value = vec![String::from("a"), String::from("bc"), String::new()];
+
+    let expected = usable_size(value.as_ptr())
+        + usable_size(value[0].as_ptr())
+        + usable_size(value[1].as_ptr());
+
+    assert_eq!(value.get_heap_size_with_model(model()), expected);
+
+    let value = Test {
+        name: String::from("Hello"),
+        values: Box::new([1, 2, 3]),
+    };
+
+    let expected = usable_size(value.name.as_ptr()) + usable_size(value.values.as_ptr());
+
+    assert_eq!(value.get_heap_size_with_model(model()), expected);
+}
+
+#[test]
+fn malloc_usable_size_falls_back_to_layout() {
+    let mut value = HashMap::with_capacity(4);
+    value.insert(1u64, 2u64);
+
+    assert_eq!(
+        value.get_heap_size_with_model(model()),
+        value.get_heap_size()
+    );
+}
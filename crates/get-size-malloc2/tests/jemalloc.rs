@@ -0,0 +1,43 @@
+#![cfg(feature = "jemalloc")]
+
+use get_size_malloc2::JemallocUsableSize;
+use get_size2::GetSize;
+use tikv_jemallocator::Jemalloc;
+
+#[global_allocator]
+static GLOBAL: Jemalloc = Jemalloc;
+
+#[expect(unsafe_code, reason = "The tests use jemalloc as the global allocator")]
+fn model() -> JemallocUsableSize {
+    unsafe { JemallocUsableSize::new() }
+}
+
+#[expect(unsafe_code, reason = "Queries jemalloc directly")]
+fn usable_size<T>(ptr: *const T) -> usize {
+    unsafe { tikv_jemalloc_sys::sallocx(ptr.cast(), 0) }
+}
+
+#[test]
+fn jemalloc_usable_size_of_buffers() {
+    let value: Vec<u8> = Vec::with_capacity(10);
+    let size = value.get_heap_size_with_model(model());
+    assert_eq!(size, usable_size(value.as_ptr()));
+    assert!(size >= 10, "The allocator must reserve the requested bytes");
+
+    let value = vec![String::from("Hello"), String::from("World!")];
+    assert_eq!(
+        value.get_heap_size_with_model(model()),
+        usable_size(value.as_ptr())
+            + usable_size(value[0].as_ptr())
+            + usable_size(value[1].as_ptr())
+    );
+
+    let value = Box::new([0u64; 5]);
+    assert_eq!(
+        value.get_heap_size_with_model(model()),
+        usable_size(&raw const *value)
+    );
+
+    // Nothing is allocated for empty buffers.
+    assert_eq!(Vec::<u64>::new().get_heap_size_with_model(model()), 0);
+}
@@ -25,10 +25,10 @@ fn main() {
 
     let secondary_data = SecondaryStore { id: 2, shared_data };
 
-    // Note that Arc does also store the Vec's stack data on the heap.
+    // Note that Arc does also store the Vec's stack data and its reference counters on the heap.
     assert_eq!(
         primary_data.get_heap_size(),
-        Vec::<u8>::get_stack_size() + 1024,
+        2 * std::mem::size_of::<usize>() + Vec::<u8>::get_stack_size() + 1024,
         "PrimaryStore"
     );
     assert_eq!(secondary_data.get_heap_size(), 0, "SecondaryStore");
@@ -0,0 +1,180 @@
+use std::alloc::Layout;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+/// A heap allocation of the global allocator, which is handed to an [`AllocatorModel`] in order
+/// to determine its size.
+///
+/// Only the implementations of this crate can create an `Allocation`, and only for allocations
+/// which they own. The pointer is therefore guaranteed to point to the start of a live, non-zero
+/// sized allocation of the [global allocator](std::alloc::GlobalAlloc), which has been
+/// requested with the given layout and stays alive for the lifetime `'a`.
+#[derive(Debug, Clone, Copy)]
+pub struct Allocation<'a> {
+    ptr: NonNull<u8>,
+    layout: Layout,
+    lifetime: PhantomData<&'a ()>,
+}
+
+impl Allocation<'_> {
+    /// The `ptr` must point to the start of a live allocation of the global allocator with the
+    /// given non-zero sized `layout`.
+    pub(crate) fn new<T: ?Sized>(ptr: NonNull<T>, layout: Layout) -> Self {
+        Self {
+            ptr: ptr.cast(),
+            layout,
+            lifetime: PhantomData,
+        }
+    }
+
+    /// Get the pointer to the start of the allocation.
+    #[must_use]
+    pub const fn ptr(&self) -> NonNull<u8> {
+        self.ptr
+    }
+
+    /// Get the layout the allocation has been requested with.
+    #[must_use]
+    pub const fn layout(&self) -> Layout {
+        self.layout
+    }
+}
+
+/// A model of a memory allocator, which determines how many bytes are actually reserved for a
+/// heap allocation.
+///
+/// Real allocators round every request up to one of their size classes, so summing up the
+/// requested sizes under-reports the memory used. Pass a model to
+/// [`GetSize::get_heap_size_with_model`](crate::GetSize::get_heap_size_with_model) or wrap it
+/// into an [`AllocatorModelTracker`](crate::AllocatorModelTracker) in order to round every heap
+/// allocation reported by the implementations of this crate.
+pub trait AllocatorModel {
+    /// Determines how many bytes the allocator reserves for a heap allocation with the given
+    /// non-zero sized `layout`.
+    fn allocation_size(&self, layout: Layout) -> usize;
+
+    /// Determines how many bytes the allocator reserves for the given live `allocation`.
+    ///
+    /// This is used instead of [`allocation_size`](AllocatorModel::allocation_size) whenever
+    /// the pointer to the allocation is known, which allows to query the allocator itself. The
+    /// default implementation only uses the layout of the `allocation`.
+    fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+        self.allocation_size(allocation.layout())
+    }
+}
+
+impl<M: AllocatorModel + ?Sized> AllocatorModel for &M {
+    fn allocation_size(&self, layout: Layout) -> usize {
+        AllocatorModel::allocation_size(&**self, layout)
+    }
+
+    fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+        AllocatorModel::allocation_size_of(&**self, allocation)
+    }
+}
+
+impl<M: AllocatorModel + ?Sized> AllocatorModel for Box<M> {
+    fn allocation_size(&self, layout: Layout) -> usize {
+        AllocatorModel::allocation_size(&**self, layout)
+    }
+
+    fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+        AllocatorModel::allocation_size_of(&**self, allocation)
+    }
+}
+
+/// Rounds the given `size` up to a size class of a geometric series with four size classes for
+/// each doubling, like `160`, `192`, `224` and `256`.
+const fn geometric_size_class(size: usize) -> usize {
+    let lg = usize::BITS - 1 - (size - 1).leading_zeros();
+
+    size.next_multiple_of(1 << (lg - 2))
+}
+
+/// A model of the size classes of [jemalloc](https://jemalloc.net/), using its default
+/// configuration.
+///
+/// Requests up to 8 bytes are rounded up to 8, requests up to 128 bytes to a multiple of 16,
+/// and all larger requests to one of four size classes for each doubling.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct JemallocModel;
+
+impl AllocatorModel for JemallocModel {
+    fn allocation_size(&self, layout: Layout) -> usize {
+        let size = layout.pad_to_align().size();
+
+        if size <= 8 {
+            8
+        } else if size <= 128 {
+            size.next_multiple_of(16)
+        } else {
+            geometric_size_class(size)
+        }
+    }
+}
+
+/// A model of the size classes of [mimalloc](https://github.com/microsoft/mimalloc), using
+/// its default configuration.
+///
+/// Requests up to 8 words are rounded up to a whole number of words, requests up to 128 KiB to
+/// one of four size classes for each doubling. Larger requests are rounded up to a multiple of
+/// the page or segment granularity mimalloc uses for them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MimallocModel;
+
+impl AllocatorModel for MimallocModel {
+    fn allocation_size(&self, layout: Layout) -> usize {
+        const KIB: usize = 1024;
+        const MIB: usize = 1024 * KIB;
+
+        let word = size_of::<usize>();
+        let size = layout.pad_to_align().size();
+
+        if size <= 8 * word {
+            size.next_multiple_of(word)
+        } else if size <= 128 * KIB {
+            geometric_size_class(size.div_ceil(word)) * word
+        } else {
+            let granularity = if size < 512 * KIB {
+                4 * KIB
+            } else if size < 2 * MIB {
+                64 * KIB
+            } else if size < 8 * MIB {
+                256 * KIB
+            } else if size < 32 * MIB {
+                MIB
+            } else {
+                4 * MIB
+            };
+
+            size.next_multiple_of(granularity)
+        }
+    }
+}
+
+/// A model of the allocator of the [GNU C library](https://sourceware.org/glibc/wiki/MallocInternals),
+/// using its default configuration.
+///
+/// Every chunk stores its size in front of the requested bytes and is rounded up to a multiple
+/// of two words, using a minimum of four words. Requests of at least 128 KiB are served by
+/// mapping whole pages instead. The returned size includes the size header of the chunk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct GlibcMallocModel;
+
+impl AllocatorModel for GlibcMallocModel {
+    fn allocation_size(&self, layout: Layout) -> usize {
+        const MMAP_THRESHOLD: usize = 128 * 1024;
+        const PAGE_SIZE: usize = 4096;
+
+        let word = size_of::<usize>();
+        let size = layout.pad_to_align().size();
+
+        let chunk = (size + word).next_multiple_of(2 * word).max(4 * word);
+
+        if size < MMAP_THRESHOLD {
+            chunk
+        } else {
+            (chunk + word).next_multiple_of(PAGE_SIZE)
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::any::type_name;
+use std::fmt;
+
+/// An error which prevented the size of an object from being determined, returned by the
+/// fallible methods like [`GetSize::try_get_heap_size`](crate::GetSize::try_get_heap_size).
+///
+/// Every variant names the type of the value which failed to be measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum GetSizeError {
+    /// The lock of a [`Mutex`](std::sync::Mutex) or [`RwLock`](std::sync::RwLock) was held
+    /// elsewhere while using [`LockPolicy::Fail`](crate::LockPolicy::Fail).
+    Locked {
+        /// The name of the type of the locked value.
+        type_name: &'static str,
+    },
+    /// A value was mutably borrowed elsewhere, so it could not be inspected.
+    Borrowed {
+        /// The name of the type of the borrowed value.
+        type_name: &'static str,
+    },
+    /// The size did not fit into a [`usize`].
+    Overflow {
+        /// The name of the type whose size overflowed.
+        type_name: &'static str,
+    },
+}
+
+impl GetSizeError {
+    /// Creates a [`GetSizeError::Locked`] error for a value of type `T`.
+    #[must_use]
+    pub fn locked<T: ?Sized>() -> Self {
+        Self::Locked {
+            type_name: type_name::<T>(),
+        }
+    }
+
+    /// Creates a [`GetSizeError::Borrowed`] error for a value of type `T`.
+    #[must_use]
+    pub fn borrowed<T: ?Sized>() -> Self {
+        Self::Borrowed {
+            type_name: type_name::<T>(),
+        }
+    }
+
+    /// Creates a [`GetSizeError::Overflow`] error for a value of type `T`.
+    #[must_use]
+    pub fn overflow<T: ?Sized>() -> Self {
+        Self::Overflow {
+            type_name: type_name::<T>(),
+        }
+    }
+
+    /// Get the name of the type of the value which failed to be measured.
+    #[must_use]
+    pub const fn type_name(&self) -> &'static str {
+        match self {
+            Self::Locked { type_name }
+            | Self::Borrowed { type_name }
+            | Self::Overflow { type_name } => type_name,
+        }
+    }
+}
+
+impl fmt::Display for GetSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Locked { type_name } => write!(f, "the lock of `{type_name}` is held elsewhere"),
+            Self::Borrowed { type_name } => {
+                write!(f, "`{type_name}` is mutably borrowed elsewhere")
+            }
+            Self::Overflow { type_name } => write!(f, "the size of `{type_name}` overflowed"),
+        }
+    }
+}
+
+impl std::error::Error for GetSizeError {}
+
+/// Adds `add` bytes to the `total` size of a value of type `T`, failing on overflow.
+pub(crate) fn try_add<T: ?Sized>(total: usize, add: usize) -> Result<usize, GetSizeError> {
+    total
+        .checked_add(add)
+        .ok_or_else(GetSizeError::overflow::<T>)
+}
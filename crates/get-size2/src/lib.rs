@@ -1,7 +1,12 @@
 #![doc = include_str!("./lib.md")]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+use std::alloc::Layout;
+use std::any::Any;
 use std::borrow::Cow;
+#[cfg(feature = "lazy-cell")]
+use std::cell::LazyCell;
+use std::cell::{Cell, OnceCell, RefCell};
 use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
 use std::convert::Infallible;
 use std::marker::{PhantomData, PhantomPinned};
@@ -10,18 +15,40 @@ use std::num::{
     NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize,
 };
 use std::ops::{Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive};
+use std::ptr::NonNull;
 use std::rc::{Rc, Weak as RcWeak};
+#[cfg(feature = "lazy-cell")]
+use std::sync::LazyLock;
 use std::sync::atomic::{
     AtomicBool, AtomicI8, AtomicI16, AtomicI32, AtomicI64, AtomicIsize, AtomicU8, AtomicU16,
     AtomicU32, AtomicU64, AtomicUsize, Ordering,
 };
-use std::sync::{Arc, Mutex, OnceLock, RwLock, Weak as ArcWeak};
+use std::sync::{
+    Arc, LockResult, Mutex, OnceLock, PoisonError, RwLock, TryLockError, TryLockResult,
+    Weak as ArcWeak,
+};
 use std::time::{Duration, Instant, SystemTime};
 
 #[cfg(feature = "derive")]
 #[cfg_attr(docsrs, doc(cfg(feature = "derive")))]
 pub use get_size_derive2::*;
 
+mod allocator;
+pub use allocator::*;
+mod error;
+use error::try_add;
+pub use error::*;
+#[macro_use]
+mod macros;
+#[doc(hidden)]
+pub use macros::__macro_support;
+mod remote;
+pub use remote::*;
+mod report;
+pub use report::*;
+#[cfg(feature = "testing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+pub mod testing;
 mod tracker;
 pub use tracker::*;
 #[cfg(test)]
@@ -58,6 +85,20 @@ pub trait GetSize: Sized {
         (GetSize::get_heap_size(self), tracker)
     }
 
+    /// Determines how many bytes this object occupies inside the heap, rounding every heap
+    /// allocation up to the size classes of the given allocator `model`.
+    ///
+    /// The default implementation calls [`get_heap_size_with_tracker`](Self::get_heap_size_with_tracker)
+    /// using a fresh [`StandardTracker`] wrapped into an [`AllocatorModelTracker`] and is not
+    /// meant to be changed.
+    fn get_heap_size_with_model<M: AllocatorModel>(&self, model: M) -> usize {
+        let tracker = AllocatorModelTracker::new(model, StandardTracker::default());
+
+        let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
+        total
+    }
+
     /// Determines the total size of the object.
     ///
     /// The default implementation simply adds up the results of [`get_stack_size`](Self::get_stack_size)
@@ -79,8 +120,308 @@ pub trait GetSize: Sized {
 
         (total, tracker)
     }
+
+    /// Determines a breakdown of the size of the object into its named parts while using a
+    /// `tracker`.
+    ///
+    /// The default implementation returns a single node without any children, using the results
+    /// of [`get_stack_size`](Self::get_stack_size) and [`get_heap_size_with_tracker`](Self::get_heap_size_with_tracker).
+    /// The returned node is unnamed, naming it is up to the caller.
+    fn get_size_report_with_tracker<T: GetSizeTracker>(&self, tracker: T) -> (SizeReport, T) {
+        let (heap_size, tracker) = GetSize::get_heap_size_with_tracker(self, tracker);
+
+        (
+            SizeReport::new("", Self::get_stack_size(), heap_size),
+            tracker,
+        )
+    }
+
+    /// Determines a breakdown of the size of the object into its named parts.
+    ///
+    /// The default implementation calls [`get_size_report_with_tracker`](Self::get_size_report_with_tracker)
+    /// using a fresh [`StandardTracker`] and names the returned node `root`. It is not meant to
+    /// be changed.
+    fn get_size_report(&self) -> SizeReport {
+        let tracker = StandardTracker::default();
+
+        let (report, _) = GetSize::get_size_report_with_tracker(self, tracker);
+
+        report.named("root")
+    }
+
+    /// Determines how many bytes this object occupies inside the heap, failing if a part of it
+    /// can not be measured instead of skipping it.
+    ///
+    /// The default implementation calls [`try_get_heap_size_with_tracker`](Self::try_get_heap_size_with_tracker)
+    /// using a fresh [`StandardTracker`] and is not meant to be changed.
+    ///
+    /// # Errors
+    /// If a part of the object can not be measured, or if its size overflows.
+    fn try_get_heap_size(&self) -> Result<usize, GetSizeError> {
+        let tracker = StandardTracker::default();
+
+        let (total, _) = GetSize::try_get_heap_size_with_tracker(self, tracker)?;
+
+        Ok(total)
+    }
+
+    /// Determines how many bytes this object occupies inside the heap while using a `tracker`,
+    /// failing if a part of it can not be measured instead of skipping it. The `tracker` is
+    /// dropped on failure, so pass a reference in order to keep using it.
+    ///
+    /// The default implementation calls [`get_heap_size_with_tracker`](Self::get_heap_size_with_tracker),
+    /// which never fails. It must be adjusted for objects holding values which may fail to be
+    /// measured, passing their errors on.
+    ///
+    /// # Errors
+    /// If a part of the object can not be measured, or if its size overflows.
+    fn try_get_heap_size_with_tracker<T: GetSizeTracker>(
+        &self,
+        tracker: T,
+    ) -> Result<(usize, T), GetSizeError> {
+        Ok(GetSize::get_heap_size_with_tracker(self, tracker))
+    }
+
+    /// Determines the total size of the object, failing if a part of it can not be measured.
+    ///
+    /// The default implementation adds up the results of [`get_stack_size`](Self::get_stack_size)
+    /// and [`try_get_heap_size`](Self::try_get_heap_size) and is not meant to be changed.
+    ///
+    /// # Errors
+    /// If a part of the object can not be measured, or if its size overflows.
+    fn try_get_size(&self) -> Result<usize, GetSizeError> {
+        let heap_size = GetSize::try_get_heap_size(self)?;
+
+        try_add::<Self>(Self::get_stack_size(), heap_size)
+    }
+
+    /// Determines the total size of the object while using a `tracker`, failing if a part of it
+    /// can not be measured.
+    ///
+    /// The default implementation adds up the results of [`get_stack_size`](Self::get_stack_size)
+    /// and [`try_get_heap_size_with_tracker`](Self::try_get_heap_size_with_tracker) and is not
+    /// meant to be changed.
+    ///
+    /// # Errors
+    /// If a part of the object can not be measured, or if its size overflows.
+    fn try_get_size_with_tracker<T: GetSizeTracker>(
+        &self,
+        tracker: T,
+    ) -> Result<(usize, T), GetSizeError> {
+        let (heap_size, tracker) = GetSize::try_get_heap_size_with_tracker(self, tracker)?;
+
+        Ok((try_add::<Self>(Self::get_stack_size(), heap_size)?, tracker))
+    }
+}
+
+/// Determines the merged report of all the given `elements`, named `[*]`.
+fn get_elements_report<'a, T, I, TR>(elements: I, mut tracker: TR) -> (SizeReport, TR)
+where
+    T: GetSize + 'a,
+    I: IntoIterator<Item = &'a T>,
+    TR: GetSizeTracker,
+{
+    let mut report = SizeReport::new("[*]", 0, 0);
+
+    for element in elements {
+        let (element, tr) = GetSize::get_size_report_with_tracker(element, tracker);
+        report.merge(element);
+        tracker = tr;
+    }
+
+    (report, tracker)
 }
 
+/// Determines the merged reports of all the keys and values of the given `entries`, named
+/// `[key]` and `[value]`.
+fn get_entries_report<'a, K, V, I, TR>(entries: I, mut tracker: TR) -> (SizeReport, SizeReport, TR)
+where
+    K: GetSize + 'a,
+    V: GetSize + 'a,
+    I: IntoIterator<Item = (&'a K, &'a V)>,
+    TR: GetSizeTracker,
+{
+    let mut keys = SizeReport::new("[key]", 0, 0);
+    let mut values = SizeReport::new("[value]", 0, 0);
+
+    for (k, v) in entries {
+        let (key, tr) = GetSize::get_size_report_with_tracker(k, tracker);
+        keys.merge(key);
+        let (value, tr) = GetSize::get_size_report_with_tracker(v, tr);
+        values.merge(value);
+        tracker = tr;
+    }
+
+    (keys, values, tracker)
+}
+
+/// An object safe companion of [`GetSize`], which can be used to determine the size of unsized
+/// types and trait objects.
+///
+/// This trait is implemented for every type implementing [`GetSize`], as well as for slices and
+/// [`str`]. Use it as a supertrait of your own traits in order to measure trait objects like
+/// `Box<dyn Plugin>` or `Arc<dyn Handler>`.
+pub trait DynGetSize {
+    /// Determines how many bytes the value itself occupies, which is [`std::mem::size_of_val`]
+    /// for all but the most exotic types.
+    fn get_dyn_stack_size(&self) -> usize;
+
+    /// Determines how many bytes this object occupies inside the heap while using a type-erased
+    /// `tracker`.
+    fn get_dyn_heap_size_with_tracker(&self, tracker: &mut dyn DynGetSizeTracker) -> usize;
+
+    /// Determines how many bytes this object occupies inside the heap.
+    ///
+    /// The default implementation calls [`get_dyn_heap_size_with_tracker`](Self::get_dyn_heap_size_with_tracker)
+    /// using a fresh [`StandardTracker`].
+    fn get_dyn_heap_size(&self) -> usize {
+        let mut tracker = StandardTracker::default();
+
+        DynGetSize::get_dyn_heap_size_with_tracker(self, &mut tracker)
+    }
+
+    /// Determines the total size of the object while using a type-erased `tracker`.
+    ///
+    /// The default implementation simply adds up the results of [`get_dyn_stack_size`](Self::get_dyn_stack_size)
+    /// and [`get_dyn_heap_size_with_tracker`](Self::get_dyn_heap_size_with_tracker) and is not
+    /// meant to be changed.
+    fn get_dyn_size_with_tracker(&self, tracker: &mut dyn DynGetSizeTracker) -> usize {
+        DynGetSize::get_dyn_stack_size(self)
+            + DynGetSize::get_dyn_heap_size_with_tracker(self, tracker)
+    }
+
+    /// Determines the total size of the object.
+    ///
+    /// The default implementation simply adds up the results of [`get_dyn_stack_size`](Self::get_dyn_stack_size)
+    /// and [`get_dyn_heap_size`](Self::get_dyn_heap_size) and is not meant to be changed.
+    fn get_dyn_size(&self) -> usize {
+        DynGetSize::get_dyn_stack_size(self) + DynGetSize::get_dyn_heap_size(self)
+    }
+
+    /// Determines a breakdown of the size of the object into its named parts while using a
+    /// type-erased `tracker`.
+    ///
+    /// The default implementation returns a single unnamed node without any children.
+    fn get_dyn_size_report_with_tracker(&self, tracker: &mut dyn DynGetSizeTracker) -> SizeReport {
+        let heap_size = DynGetSize::get_dyn_heap_size_with_tracker(self, tracker);
+
+        SizeReport::new("", DynGetSize::get_dyn_stack_size(self), heap_size)
+    }
+
+    /// Determines how many bytes this object occupies inside the heap while using a type-erased
+    /// `tracker`, failing if a part of it can not be measured.
+    ///
+    /// The default implementation calls [`get_dyn_heap_size_with_tracker`](Self::get_dyn_heap_size_with_tracker),
+    /// which never fails.
+    ///
+    /// # Errors
+    /// If a part of the object can not be measured, or if its size overflows.
+    fn try_get_dyn_heap_size_with_tracker(
+        &self,
+        tracker: &mut dyn DynGetSizeTracker,
+    ) -> Result<usize, GetSizeError> {
+        Ok(DynGetSize::get_dyn_heap_size_with_tracker(self, tracker))
+    }
+}
+
+impl<T> DynGetSize for T
+where
+    T: GetSize,
+{
+    fn get_dyn_stack_size(&self) -> usize {
+        T::get_stack_size()
+    }
+
+    fn get_dyn_heap_size_with_tracker(&self, tracker: &mut dyn DynGetSizeTracker) -> usize {
+        let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
+        total
+    }
+
+    fn get_dyn_heap_size(&self) -> usize {
+        GetSize::get_heap_size(self)
+    }
+
+    fn get_dyn_size_report_with_tracker(&self, tracker: &mut dyn DynGetSizeTracker) -> SizeReport {
+        let (report, _) = GetSize::get_size_report_with_tracker(self, tracker);
+
+        report
+    }
+
+    fn try_get_dyn_heap_size_with_tracker(
+        &self,
+        tracker: &mut dyn DynGetSizeTracker,
+    ) -> Result<usize, GetSizeError> {
+        let (total, _) = GetSize::try_get_heap_size_with_tracker(self, tracker)?;
+
+        Ok(total)
+    }
+}
+
+impl<T> DynGetSize for [T]
+where
+    T: GetSize,
+{
+    fn get_dyn_stack_size(&self) -> usize {
+        // The slice stack size already accounts for the stack size of its elements.
+        std::mem::size_of_val(self)
+    }
+
+    fn get_dyn_heap_size_with_tracker(&self, mut tracker: &mut dyn DynGetSizeTracker) -> usize {
+        let mut total = 0;
+
+        for element in self {
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(element, tracker);
+            total += total_add;
+            tracker = tr;
+        }
+
+        total
+    }
+
+    fn get_dyn_size_report_with_tracker(&self, tracker: &mut dyn DynGetSizeTracker) -> SizeReport {
+        let mut report = SizeReport::new("", std::mem::size_of_val(self), 0);
+
+        let (elements, _) = get_elements_report(self, tracker);
+        report.add_inline_child(elements);
+
+        report
+    }
+
+    fn try_get_dyn_heap_size_with_tracker(
+        &self,
+        mut tracker: &mut dyn DynGetSizeTracker,
+    ) -> Result<usize, GetSizeError> {
+        let mut total = 0;
+
+        for element in self {
+            let (total_add, tr) = GetSize::try_get_heap_size_with_tracker(element, tracker)?;
+            total = try_add::<Self>(total, total_add)?;
+            tracker = tr;
+        }
+
+        Ok(total)
+    }
+}
+
+macro_rules! impl_dyn_size_unsized {
+    ($($name:ty),+) => {
+        $(
+            impl DynGetSize for $name {
+                fn get_dyn_stack_size(&self) -> usize {
+                    std::mem::size_of_val(self)
+                }
+
+                fn get_dyn_heap_size_with_tracker(&self, _tracker: &mut dyn DynGetSizeTracker) -> usize {
+                    0
+                }
+            }
+        )+
+    };
+}
+
+impl_dyn_size_unsized!(str, std::ffi::CStr, std::ffi::OsStr, std::path::Path);
+
 impl GetSize for () {}
 impl GetSize for bool {}
 impl GetSize for u8 {}
@@ -127,38 +468,17 @@ impl GetSize for Ordering {}
 impl GetSize for std::cmp::Ordering {}
 
 impl GetSize for Infallible {}
-impl<T> GetSize for PhantomData<T> {}
+impl<T: ?Sized> GetSize for PhantomData<T> {}
 impl GetSize for PhantomPinned {}
 
 impl GetSize for Instant {}
 impl GetSize for Duration {}
 impl GetSize for SystemTime {}
 
-/// This macro is similar to the derive macro; Generate a `GetSize` impl that
-/// adds the heap sizes of the fields that are specified. However, since we want
-/// to implement this also for third-party types where we cannot add the derive
-/// macro, we go this way.
-///
-/// # Examples
-/// ```ignore
-/// impl_sum_of_fields!(Range, start, end);
-/// impl_sum_of_fields!(Person, name, address, phone);
-/// ```
-macro_rules! impl_sum_of_fields {
-    ($name:ident, $($field:ident),+) => {
-        impl<I: GetSize> GetSize for $name<I> {
-            #[inline]
-            fn get_heap_size(&self) -> usize {
-                0 $(+ self.$field.get_heap_size())+
-            }
-        }
-    };
-}
-
-impl_sum_of_fields!(Range, start, end);
-impl_sum_of_fields!(RangeFrom, start);
-impl_sum_of_fields!(RangeTo, end);
-impl_sum_of_fields!(RangeToInclusive, end);
+impl_get_size!(impl<I> fields for Range<I> { start, end } where I: GetSize);
+impl_get_size!(impl<I> fields for RangeFrom<I> { start } where I: GetSize);
+impl_get_size!(impl<I> fields for RangeTo<I> { end } where I: GetSize);
+impl_get_size!(impl<I> fields for RangeToInclusive<I> { end } where I: GetSize);
 impl GetSize for RangeFull {}
 
 impl<I: GetSize> GetSize for RangeInclusive<I> {
@@ -167,6 +487,23 @@ impl<I: GetSize> GetSize for RangeInclusive<I> {
         // Custom impl since start and end fields are not public API
         (*self.start()).get_heap_size() + (*self.end()).get_heap_size()
     }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+        let (start, tracker) = GetSize::get_heap_size_with_tracker(self.start(), tracker);
+        let (end, tracker) = GetSize::get_heap_size_with_tracker(self.end(), tracker);
+
+        (start + end, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let (start, tracker) = GetSize::try_get_heap_size_with_tracker(self.start(), tracker)?;
+        let (end, tracker) = GetSize::try_get_heap_size_with_tracker(self.end(), tracker)?;
+
+        Ok((try_add::<Self>(start, end)?, tracker))
+    }
 }
 
 impl<T> GetSize for Cow<'_, T>
@@ -180,55 +517,203 @@ where
             Self::Owned(owned) => GetSize::get_heap_size(owned),
         }
     }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+        match self {
+            Self::Borrowed(_borrowed) => (0, tracker),
+            Self::Owned(owned) => GetSize::get_heap_size_with_tracker(owned, tracker),
+        }
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        match self {
+            Self::Borrowed(_borrowed) => Ok((0, tracker)),
+            Self::Owned(owned) => GetSize::try_get_heap_size_with_tracker(owned, tracker),
+        }
+    }
 }
 
-macro_rules! impl_size_set {
-    ($name:ident) => {
-        impl<T> GetSize for $name<T>
-        where
-            T: GetSize,
-        {
-            fn get_heap_size(&self) -> usize {
-                let mut total = 0;
+impl_get_size!(impl<T> collection for BinaryHeap<T> where T: GetSize);
+impl_get_size!(impl<T> collection for VecDeque<T> where T: GetSize);
 
-                for v in self.iter() {
-                    // We assume that value are hold inside the heap.
-                    total += GetSize::get_size(v);
-                }
+/// Determines the layout of an array of `len` values of type `T`, like the buffer of a [`Vec`]
+/// with a capacity of `len`.
+fn array_layout<T>(len: usize) -> Layout {
+    Layout::array::<T>(len).expect("The array does already exist in memory")
+}
 
-                let additional: usize = self.capacity() - self.len();
-                total += additional * T::get_stack_size();
+/// Determines how many bytes the allocator reserves for a heap allocation with the given
+/// `layout`, according to the `tracker`.
+fn allocation_size<TR: GetSizeTracker>(tracker: &TR, layout: Layout) -> usize {
+    // Zero sized values are never allocated.
+    if layout.size() == 0 {
+        0
+    } else {
+        tracker.allocation_size(layout)
+    }
+}
 
-                total
-            }
-        }
-    };
+/// Determines how many bytes the allocator reserves for the heap allocation at `ptr` with the
+/// given `layout`, according to the `tracker`.
+///
+/// The `ptr` must point to the start of a live allocation of the global allocator, unless the
+/// `layout` is zero sized.
+fn allocation_size_of<T: ?Sized, TR: GetSizeTracker>(
+    tracker: &TR,
+    ptr: NonNull<T>,
+    layout: Layout,
+) -> usize {
+    // Zero sized values are never allocated, their pointers are dangling.
+    if layout.size() == 0 {
+        0
+    } else {
+        tracker.allocation_size_of(Allocation::new(ptr, layout))
+    }
 }
 
-macro_rules! impl_size_set_no_capacity {
-    ($name:ident) => {
-        impl<T> GetSize for $name<T>
-        where
-            T: GetSize,
-        {
-            fn get_heap_size(&self) -> usize {
-                let mut total = 0;
+/// Determines the layout of a single node of a [`LinkedList`], which stores the pointers to the
+/// next and the previous node next to a value of type `T`.
+fn linked_list_node_layout<T>() -> Layout {
+    let align = std::mem::align_of::<T>().max(std::mem::align_of::<usize>());
+    let size =
+        (2 * std::mem::size_of::<usize>() + std::mem::size_of::<T>()).next_multiple_of(align);
 
-                for v in self.iter() {
-                    // We assume that value are hold inside the heap.
-                    total += GetSize::get_size(v);
-                }
+    Layout::from_size_align(size, align).expect("The node layout is valid")
+}
 
-                total
-            }
+impl<T> GetSize for LinkedList<T>
+where
+    T: GetSize,
+{
+    fn get_heap_size(&self) -> usize {
+        let tracker = StandardTracker::default();
+
+        let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
+        total
+    }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
+        let mut total = self.len() * allocation_size(&tracker, linked_list_node_layout::<T>());
+
+        for v in self {
+            // The values are stored inside the nodes themselves.
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(v, tracker);
+            total += total_add;
+            tracker = tr;
         }
-    };
+
+        (total, tracker)
+    }
+
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (SizeReport, TR) {
+        let node = allocation_size(&tracker, linked_list_node_layout::<T>());
+        let mut report = SizeReport::new(
+            "",
+            Self::get_stack_size(),
+            self.len() * (node - T::get_stack_size()),
+        );
+
+        let (elements, tracker) = get_elements_report(self, tracker);
+        report.add_heap_child(elements);
+
+        (report, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let nodes = allocation_size(&tracker, linked_list_node_layout::<T>())
+            .checked_mul(self.len())
+            .ok_or_else(GetSizeError::overflow::<Self>)?;
+
+        // The values are stored inside the nodes themselves.
+        __macro_support::try_buffer_heap_size_with_tracker(self, Some(nodes), tracker)
+    }
 }
 
-impl_size_set_no_capacity!(BTreeSet);
-impl_size_set!(BinaryHeap);
-impl_size_set_no_capacity!(LinkedList);
-impl_size_set!(VecDeque);
+/// How densely the nodes of a [`BTreeMap`] or [`BTreeSet`] are assumed to be filled.
+///
+/// The number of nodes backing a tree can not be observed from the outside, so it is estimated
+/// from the number of elements instead. Use [`StandardTracker::set_btree_occupancy`] to choose
+/// the estimate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum BTreeOccupancy {
+    /// Every node holds as many elements as possible, which is the case for trees collected
+    /// from a sorted iterator. This is the best case and thus a lower bound.
+    #[default]
+    Full,
+    /// Every node only holds the minimum number of elements, which may happen after many
+    /// insertions and removals. This is the worst case and thus an upper bound.
+    Minimal,
+}
+
+/// The maximum number of elements stored inside a single node of a [`BTreeMap`].
+const BTREE_NODE_CAPACITY: usize = 11;
+
+/// The minimum number of elements stored inside a single node of a [`BTreeMap`], except for
+/// its root.
+const BTREE_NODE_MIN_LEN: usize = 5;
+
+/// Determines the size of all nodes backing a [`BTreeMap`] or [`BTreeSet`] with `len` keys of
+/// type `K` and values of type `V`, according to the `tracker`.
+///
+/// Leaf nodes store up to 11 keys and values next to a parent pointer and two `u16` indices,
+/// internal nodes additionally store 12 pointers to their children.
+fn btree_allocation_size<K, V, TR: GetSizeTracker>(len: usize, tracker: &TR) -> usize {
+    if len == 0 {
+        return 0;
+    }
+
+    let align = std::mem::align_of::<usize>()
+        .max(std::mem::align_of::<K>())
+        .max(std::mem::align_of::<V>());
+    let leaf_size = (std::mem::size_of::<usize>()
+        + 2 * std::mem::size_of::<u16>()
+        + BTREE_NODE_CAPACITY * (std::mem::size_of::<K>() + std::mem::size_of::<V>()))
+    .next_multiple_of(align);
+    let internal_size = (leaf_size + (BTREE_NODE_CAPACITY + 1) * std::mem::size_of::<usize>())
+        .next_multiple_of(align);
+
+    let node_size = |size| {
+        let layout = Layout::from_size_align(size, align).expect("The node layout is valid");
+
+        allocation_size(tracker, layout)
+    };
+    let leaf = node_size(leaf_size);
+    let internal = node_size(internal_size);
+    let occupancy = tracker.options().btree_occupancy;
+
+    // Every node but the last one of each level is separated from the next one by a key stored
+    // inside the level above.
+    let node_len = match occupancy {
+        BTreeOccupancy::Full => BTREE_NODE_CAPACITY,
+        BTreeOccupancy::Minimal => BTREE_NODE_MIN_LEN,
+    };
+    let nodes_for = |children: usize| match occupancy {
+        BTreeOccupancy::Full => children.div_ceil(node_len + 1),
+        BTreeOccupancy::Minimal => (children / (node_len + 1)).max(1),
+    };
+
+    let leaves = if len <= BTREE_NODE_CAPACITY {
+        1
+    } else {
+        nodes_for(len + 1)
+    };
+
+    let mut internals = 0;
+    let mut nodes = leaves;
+    while nodes > 1 {
+        nodes = nodes_for(nodes);
+        internals += nodes;
+    }
+
+    leaves * leaf + internals * internal
+}
 
 impl<K, V> GetSize for BTreeMap<K, V>
 where
@@ -236,13 +721,152 @@ where
     V: GetSize,
 {
     fn get_heap_size(&self) -> usize {
-        let mut total = 0;
+        let tracker = StandardTracker::default();
+
+        let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
+        total
+    }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
+        let mut total = btree_allocation_size::<K, V, _>(self.len(), &tracker);
+
         for (k, v) in self {
-            total += GetSize::get_size(k);
-            total += GetSize::get_size(v);
+            // The keys and values are stored inside the nodes themselves.
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(k, tracker);
+            total += total_add;
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(v, tr);
+            total += total_add;
+            tracker = tr;
         }
+
+        (total, tracker)
+    }
+
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (SizeReport, TR) {
+        let nodes = btree_allocation_size::<K, V, _>(self.len(), &tracker);
+        let mut report = SizeReport::new(
+            "",
+            Self::get_stack_size(),
+            nodes - self.len() * (K::get_stack_size() + V::get_stack_size()),
+        );
+
+        let (keys, values, tracker) = get_entries_report(self, tracker);
+        report.add_heap_child(keys);
+        report.add_heap_child(values);
+
+        (report, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let nodes = btree_allocation_size::<K, V, _>(self.len(), &tracker);
+
+        __macro_support::try_entry_buffer_heap_size_with_tracker(self, Some(nodes), tracker)
+    }
+}
+
+impl<T> GetSize for BTreeSet<T>
+where
+    T: GetSize,
+{
+    fn get_heap_size(&self) -> usize {
+        let tracker = StandardTracker::default();
+
+        let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
         total
     }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
+        let mut total = btree_allocation_size::<T, (), _>(self.len(), &tracker);
+
+        for v in self {
+            // The values are stored inside the nodes themselves.
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(v, tracker);
+            total += total_add;
+            tracker = tr;
+        }
+
+        (total, tracker)
+    }
+
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (SizeReport, TR) {
+        let nodes = btree_allocation_size::<T, (), _>(self.len(), &tracker);
+        let mut report = SizeReport::new(
+            "",
+            Self::get_stack_size(),
+            nodes - self.len() * T::get_stack_size(),
+        );
+
+        let (elements, tracker) = get_elements_report(self, tracker);
+        report.add_heap_child(elements);
+
+        (report, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let nodes = btree_allocation_size::<T, (), _>(self.len(), &tracker);
+
+        __macro_support::try_buffer_heap_size_with_tracker(self, Some(nodes), tracker)
+    }
+}
+
+/// The number of control bytes probed at once by the hash table backing [`HashMap`] and
+/// [`HashSet`], which depends on the SIMD instructions available to the target.
+const HASH_TABLE_GROUP_WIDTH: usize = if cfg!(all(
+    any(
+        all(
+            target_feature = "sse2",
+            any(target_arch = "x86", target_arch = "x86_64")
+        ),
+        all(target_arch = "loongarch64", target_feature = "lsx")
+    ),
+    not(miri)
+)) {
+    16
+} else if cfg!(any(
+    target_pointer_width = "64",
+    target_arch = "aarch64",
+    target_arch = "wasm32"
+)) {
+    8
+} else {
+    4
+};
+
+/// Determines the layout of the allocation backing a [`HashMap`] or [`HashSet`] with the given
+/// `capacity`, which stores its elements of type `T` in an open addressing hash table.
+///
+/// The table consists of a power of two number of buckets, followed by one control byte per
+/// bucket and a trailing group of control bytes. Only 7/8 of the buckets are usable, tables with
+/// less than 8 buckets instead keep a single bucket empty.
+///
+/// The number of buckets is derived from the capacity, since the standard library does not expose
+/// it. Removing entries from a crowded table may however leave tombstones behind, which reduce the
+/// capacity until the table is rehashed. Such a table is under-counted as a smaller table.
+fn hash_table_layout<T>(capacity: usize) -> Layout {
+    if capacity == 0 {
+        return Layout::new::<()>();
+    }
+
+    let buckets = if capacity < 8 {
+        (capacity + 1).next_power_of_two().max(4)
+    } else {
+        (capacity * 8).div_ceil(7).next_power_of_two()
+    };
+    let ctrl_align = std::mem::align_of::<T>().max(HASH_TABLE_GROUP_WIDTH);
+    let ctrl_offset = array_layout::<T>(buckets)
+        .size()
+        .next_multiple_of(ctrl_align);
+
+    Layout::from_size_align(ctrl_offset + buckets + HASH_TABLE_GROUP_WIDTH, ctrl_align)
+        .expect("The table does already exist in memory")
 }
 
 impl<K, V, S: ::std::hash::BuildHasher> GetSize for HashMap<K, V, S>
@@ -251,15 +875,50 @@ where
     V: GetSize,
 {
     fn get_heap_size(&self) -> usize {
-        let mut total = 0;
+        let tracker = StandardTracker::default();
+
+        let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
+        total
+    }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
+        let mut total = allocation_size(&tracker, hash_table_layout::<(K, V)>(self.capacity()));
+
         for (k, v) in self {
-            total += GetSize::get_size(k);
-            total += GetSize::get_size(v);
+            // The keys and values are stored inside the table itself.
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(k, tracker);
+            total += total_add;
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(v, tr);
+            total += total_add;
+            tracker = tr;
         }
-        let additional: usize = self.capacity() - self.len();
-        total += additional * K::get_stack_size();
-        total += additional * V::get_stack_size();
-        total
+
+        (total, tracker)
+    }
+
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (SizeReport, TR) {
+        let table = allocation_size(&tracker, hash_table_layout::<(K, V)>(self.capacity()));
+        let mut report = SizeReport::new(
+            "",
+            Self::get_stack_size(),
+            table - self.len() * (K::get_stack_size() + V::get_stack_size()),
+        );
+
+        let (keys, values, tracker) = get_entries_report(self, tracker);
+        report.add_heap_child(keys);
+        report.add_heap_child(values);
+
+        (report, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let table = allocation_size(&tracker, hash_table_layout::<(K, V)>(self.capacity()));
+
+        __macro_support::try_entry_buffer_heap_size_with_tracker(self, Some(table), tracker)
     }
 }
 
@@ -268,17 +927,94 @@ where
     T: GetSize,
 {
     fn get_heap_size(&self) -> usize {
-        let mut total = 0;
+        let tracker = StandardTracker::default();
+
+        let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
+        total
+    }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
+        let mut total = allocation_size(&tracker, hash_table_layout::<T>(self.capacity()));
+
         for v in self {
-            total += GetSize::get_size(v);
+            // The values are stored inside the table itself.
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(v, tracker);
+            total += total_add;
+            tracker = tr;
         }
-        let additional: usize = self.capacity() - self.len();
-        total += additional * T::get_stack_size();
+
+        (total, tracker)
+    }
+
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (SizeReport, TR) {
+        let table = allocation_size(&tracker, hash_table_layout::<T>(self.capacity()));
+        let mut report = SizeReport::new(
+            "",
+            Self::get_stack_size(),
+            table - self.len() * T::get_stack_size(),
+        );
+
+        let (elements, tracker) = get_elements_report(self, tracker);
+        report.add_heap_child(elements);
+
+        (report, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let table = allocation_size(&tracker, hash_table_layout::<T>(self.capacity()));
+
+        __macro_support::try_buffer_heap_size_with_tracker(self, Some(table), tracker)
+    }
+}
+impl<T> GetSize for Vec<T>
+where
+    T: GetSize,
+{
+    fn get_heap_size(&self) -> usize {
+        let tracker = StandardTracker::default();
+
+        let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
         total
     }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+        let buffer = vec_buffer_size(self, &tracker);
+
+        __macro_support::buffer_heap_size_with_tracker(self, Some(buffer), tracker)
+    }
+
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (SizeReport, TR) {
+        let buffer = vec_buffer_size(self, &tracker);
+
+        __macro_support::buffer_size_report_with_tracker(
+            Self::get_stack_size(),
+            self,
+            Some(buffer),
+            tracker,
+        )
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let buffer = vec_buffer_size(self, &tracker);
+
+        __macro_support::try_buffer_heap_size_with_tracker(self, Some(buffer), tracker)
+    }
 }
 
-impl_size_set!(Vec);
+/// Determines how many bytes the allocator reserves for the buffer of the given `vec`.
+fn vec_buffer_size<T, TR: GetSizeTracker>(vec: &Vec<T>, tracker: &TR) -> usize {
+    let ptr = NonNull::new(vec.as_ptr().cast_mut()).expect("The buffer of a Vec is never null");
+
+    allocation_size_of(tracker, ptr, array_layout::<T>(vec.capacity()))
+}
 
 macro_rules! impl_size_tuple {
     ($($t:ident, $T:ident),+) => {
@@ -289,14 +1025,38 @@ macro_rules! impl_size_tuple {
             )*
         {
             fn get_heap_size(&self) -> usize {
+                let tracker = StandardTracker::default();
+
+                let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
+                total
+            }
+
+            fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
                 let mut total = 0;
 
                 let ($($t,)*) = self;
                 $(
-                    total += GetSize::get_heap_size($t);
+                    let (total_add, tracker) = GetSize::get_heap_size_with_tracker($t, tracker);
+                    total += total_add;
                 )*
 
-                total
+                (total, tracker)
+            }
+
+            fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+                &self,
+                tracker: TR,
+            ) -> Result<(usize, TR), GetSizeError> {
+                let mut total = 0;
+
+                let ($($t,)*) = self;
+                $(
+                    let (total_add, tracker) = GetSize::try_get_heap_size_with_tracker($t, tracker)?;
+                    total = try_add::<Self>(total, total_add)?;
+                )*
+
+                Ok((total, tracker))
             }
         }
     }
@@ -354,36 +1114,154 @@ where
     T: GetSize,
 {
     fn get_heap_size(&self) -> usize {
+        let tracker = StandardTracker::default();
+
+        let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
+        total
+    }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
         let mut total = 0;
 
         for element in self {
             // The array stack size already accounts for the stack size of the elements of the array.
-            total += GetSize::get_heap_size(element);
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(element, tracker);
+            total += total_add;
+            tracker = tr;
         }
 
-        total
+        (total, tracker)
+    }
+
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (SizeReport, TR) {
+        let mut report = SizeReport::new("", Self::get_stack_size(), 0);
+
+        let (elements, tracker) = get_elements_report(self, tracker);
+        report.add_inline_child(elements);
+
+        (report, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        // The array stack size already accounts for the stack size of the elements of the array.
+        __macro_support::try_buffer_heap_size_with_tracker(self, Some(0), tracker)
+    }
+}
+
+impl<T> GetSize for &[T] where T: GetSize {}
+
+impl<T> GetSize for &T {}
+impl<T> GetSize for &mut T {}
+impl<T> GetSize for *const T {}
+impl<T> GetSize for *mut T {}
+
+impl<T> GetSize for Box<T>
+where
+    T: DynGetSize + ?Sized,
+{
+    fn get_heap_size(&self) -> usize {
+        let tracker = StandardTracker::default();
+
+        let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
+        total
+    }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
+        let layout = Layout::for_value(&**self);
+
+        let mut total =
+            allocation_size_of(&tracker, NonNull::from(&**self), layout) - layout.size();
+        total += DynGetSize::get_dyn_size_with_tracker(&**self, &mut tracker);
+
+        (total, tracker)
+    }
+
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(
+        &self,
+        mut tracker: TR,
+    ) -> (SizeReport, TR) {
+        let layout = Layout::for_value(&**self);
+        let mut report = SizeReport::new(
+            "",
+            Self::get_stack_size(),
+            allocation_size_of(&tracker, NonNull::from(&**self), layout) - layout.size(),
+        );
+
+        let value = DynGetSize::get_dyn_size_report_with_tracker(&**self, &mut tracker);
+        report.absorb_heap(value);
+
+        (report, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        mut tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let layout = Layout::for_value(&**self);
+
+        let mut total = allocation_size_of(&tracker, NonNull::from(&**self), layout);
+        let heap_size = DynGetSize::try_get_dyn_heap_size_with_tracker(&**self, &mut tracker)?;
+        total = try_add::<Self>(total, heap_size)?;
+
+        Ok((total, tracker))
     }
 }
 
-impl<T> GetSize for &[T] where T: GetSize {}
-
-impl<T> GetSize for &T {}
-impl<T> GetSize for &mut T {}
-impl<T> GetSize for *const T {}
-impl<T> GetSize for *mut T {}
+/// Which reference to a shared ownership object like an [`Rc`] or [`Arc`] is passed to
+/// [`GetSizeTracker::track`], which keeps the object alive for as long as the tracker stores it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TrackingMode {
+    /// A strong reference, keeping the value alive. This requires updating the reference
+    /// counter of every tracked object.
+    #[default]
+    Strong,
+    /// A weak reference, which keeps the memory of the allocation alive, so its address can not
+    /// be reused, while the value itself may still be dropped.
+    Weak,
+    /// No reference at all, passing `()` instead. The objects must be kept alive in some other
+    /// way, like the [`ScopedTracker`] does by borrowing the measured value.
+    Address,
+}
 
-impl<T> GetSize for Box<T>
+/// Tracks the shared allocation located at `addr`, passing the reference to it which is requested
+/// by the tracking mode of the `tracker`.
+fn track_shared<TR, S, W>(
+    tracker: &mut TR,
+    addr: *const (),
+    strong_ref: impl FnOnce() -> S,
+    weak_ref: impl FnOnce() -> W,
+) -> bool
 where
-    T: GetSize,
+    TR: GetSizeTracker,
+    S: Any + 'static,
+    W: Any + 'static,
 {
-    fn get_heap_size(&self) -> usize {
-        GetSize::get_size(&**self)
+    match tracker.options().tracking_mode {
+        TrackingMode::Strong => tracker.track(addr, strong_ref()),
+        TrackingMode::Weak => tracker.track(addr, weak_ref()),
+        TrackingMode::Address => tracker.track(addr, ()),
     }
 }
 
+/// Determines the layout of the allocation backing an [`Rc`] or [`Arc`], which stores the strong
+/// and weak reference counters in front of a value with the given layout.
+fn shared_allocation_layout(value: Layout) -> Layout {
+    let counters = Layout::new::<[usize; 2]>();
+
+    counters
+        .extend(value)
+        .map_or(counters, |(layout, _)| layout)
+        .pad_to_align()
+}
+
 impl<T> GetSize for Rc<T>
 where
-    T: GetSize + 'static,
+    T: DynGetSize + ?Sized + 'static,
 {
     fn get_heap_size(&self) -> usize {
         let tracker = StandardTracker::default();
@@ -394,23 +1272,109 @@ where
     }
 
     fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
-        let strong_ref = Self::clone(self);
+        let addr = Self::as_ptr(self).cast::<()>();
+
+        if track_shared(
+            &mut tracker,
+            addr,
+            || Self::clone(self),
+            || Self::downgrade(self),
+        ) {
+            let mut total = allocation_size(
+                &tracker,
+                shared_allocation_layout(Layout::for_value(&**self)),
+            );
+            total += DynGetSize::get_dyn_heap_size_with_tracker(&**self, &mut tracker);
+            tracker.track_finished(addr);
+
+            (total, tracker)
+        } else {
+            (0, tracker)
+        }
+    }
 
-        let addr = Self::as_ptr(&strong_ref);
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(
+        &self,
+        mut tracker: TR,
+    ) -> (SizeReport, TR) {
+        let mut report = SizeReport::new("", Self::get_stack_size(), 0);
+
+        let addr = Self::as_ptr(self).cast::<()>();
+
+        if track_shared(
+            &mut tracker,
+            addr,
+            || Self::clone(self),
+            || Self::downgrade(self),
+        ) {
+            // Everything reachable through the shared allocation is shared.
+            report.add_shared_heap(allocation_size(
+                &tracker,
+                shared_allocation_layout(Layout::for_value(&**self)),
+            ));
+
+            let value = DynGetSize::get_dyn_size_report_with_tracker(&**self, &mut tracker);
+            tracker.track_finished(addr);
+            report.absorb_inline(value.into_shared());
+        }
+
+        (report, tracker)
+    }
 
-        if tracker.track(addr, strong_ref) {
-            GetSize::get_size_with_tracker(&**self, tracker)
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        mut tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let addr = Self::as_ptr(self).cast::<()>();
+
+        if track_shared(
+            &mut tracker,
+            addr,
+            || Self::clone(self),
+            || Self::downgrade(self),
+        ) {
+            let total = allocation_size(
+                &tracker,
+                shared_allocation_layout(Layout::for_value(&**self)),
+            );
+            let heap_size = DynGetSize::try_get_dyn_heap_size_with_tracker(&**self, &mut tracker);
+            tracker.track_finished(addr);
+
+            Ok((try_add::<Self>(total, heap_size?)?, tracker))
         } else {
-            (0, tracker)
+            Ok((0, tracker))
         }
     }
 }
 
-impl<T> GetSize for RcWeak<T> {}
+impl<T> GetSize for RcWeak<T> {
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
+        // As long as a strong reference is alive, it accounts for the allocation.
+        if !tracker.options().count_orphaned_weak
+            || self.strong_count() > 0
+            || self.ptr_eq(&Self::new())
+        {
+            return (0, tracker);
+        }
+
+        let addr = Self::as_ptr(self).cast::<()>();
+
+        // Tracked by address only, which does not require the referenced type to be `'static`.
+        if tracker.track(addr, ()) {
+            // The value itself has already been dropped, only its memory remains allocated.
+            let total = allocation_size(&tracker, shared_allocation_layout(Layout::new::<T>()));
+            tracker.track_finished(addr);
+
+            (total, tracker)
+        } else {
+            (0, tracker)
+        }
+    }
+}
 
 impl<T> GetSize for Arc<T>
 where
-    T: GetSize + 'static,
+    T: DynGetSize + ?Sized + 'static,
 {
     fn get_heap_size(&self) -> usize {
         let tracker = StandardTracker::default();
@@ -421,19 +1385,105 @@ where
     }
 
     fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
-        let strong_ref = Self::clone(self);
+        let addr = Self::as_ptr(self).cast::<()>();
+
+        if track_shared(
+            &mut tracker,
+            addr,
+            || Self::clone(self),
+            || Self::downgrade(self),
+        ) {
+            let mut total = allocation_size(
+                &tracker,
+                shared_allocation_layout(Layout::for_value(&**self)),
+            );
+            total += DynGetSize::get_dyn_heap_size_with_tracker(&**self, &mut tracker);
+            tracker.track_finished(addr);
+
+            (total, tracker)
+        } else {
+            (0, tracker)
+        }
+    }
+
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(
+        &self,
+        mut tracker: TR,
+    ) -> (SizeReport, TR) {
+        let mut report = SizeReport::new("", Self::get_stack_size(), 0);
+
+        let addr = Self::as_ptr(self).cast::<()>();
+
+        if track_shared(
+            &mut tracker,
+            addr,
+            || Self::clone(self),
+            || Self::downgrade(self),
+        ) {
+            // Everything reachable through the shared allocation is shared.
+            report.add_shared_heap(allocation_size(
+                &tracker,
+                shared_allocation_layout(Layout::for_value(&**self)),
+            ));
+
+            let value = DynGetSize::get_dyn_size_report_with_tracker(&**self, &mut tracker);
+            tracker.track_finished(addr);
+            report.absorb_inline(value.into_shared());
+        }
 
-        let addr = Self::as_ptr(&strong_ref);
+        (report, tracker)
+    }
 
-        if tracker.track(addr, strong_ref) {
-            GetSize::get_size_with_tracker(&**self, tracker)
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        mut tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let addr = Self::as_ptr(self).cast::<()>();
+
+        if track_shared(
+            &mut tracker,
+            addr,
+            || Self::clone(self),
+            || Self::downgrade(self),
+        ) {
+            let total = allocation_size(
+                &tracker,
+                shared_allocation_layout(Layout::for_value(&**self)),
+            );
+            let heap_size = DynGetSize::try_get_dyn_heap_size_with_tracker(&**self, &mut tracker);
+            tracker.track_finished(addr);
+
+            Ok((try_add::<Self>(total, heap_size?)?, tracker))
         } else {
-            (0, tracker)
+            Ok((0, tracker))
         }
     }
 }
 
-impl<T> GetSize for ArcWeak<T> {}
+impl<T> GetSize for ArcWeak<T> {
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
+        // As long as a strong reference is alive, it accounts for the allocation.
+        if !tracker.options().count_orphaned_weak
+            || self.strong_count() > 0
+            || self.ptr_eq(&Self::new())
+        {
+            return (0, tracker);
+        }
+
+        let addr = Self::as_ptr(self).cast::<()>();
+
+        // Tracked by address only, which does not require the referenced type to be `'static`.
+        if tracker.track(addr, ()) {
+            // The value itself has already been dropped, only its memory remains allocated.
+            let total = allocation_size(&tracker, shared_allocation_layout(Layout::new::<T>()));
+            tracker.track_finished(addr);
+
+            (total, tracker)
+        } else {
+            (0, tracker)
+        }
+    }
+}
 
 impl<T> GetSize for Option<T>
 where
@@ -442,6 +1492,37 @@ where
     fn get_heap_size(&self) -> usize {
         self.as_ref().map_or(0, |t| GetSize::get_heap_size(t))
     }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+        match self {
+            None => (0, tracker),
+            Some(t) => GetSize::get_heap_size_with_tracker(t, tracker),
+        }
+    }
+
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (SizeReport, TR) {
+        let mut report = SizeReport::new("", Self::get_stack_size(), 0);
+
+        match self {
+            None => (report, tracker),
+            Some(t) => {
+                let (value, tracker) = GetSize::get_size_report_with_tracker(t, tracker);
+                report.absorb_inline(value);
+
+                (report, tracker)
+            }
+        }
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        match self {
+            None => Ok((0, tracker)),
+            Some(t) => GetSize::try_get_heap_size_with_tracker(t, tracker),
+        }
+    }
 }
 
 impl<T, E> GetSize for Result<T, E>
@@ -456,38 +1537,251 @@ where
             Err(e) => GetSize::get_heap_size(e),
         }
     }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+        match self {
+            Ok(t) => GetSize::get_heap_size_with_tracker(t, tracker),
+            Err(e) => GetSize::get_heap_size_with_tracker(e, tracker),
+        }
+    }
+
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (SizeReport, TR) {
+        let mut report = SizeReport::new("", Self::get_stack_size(), 0);
+
+        let (value, tracker) = match self {
+            Ok(t) => GetSize::get_size_report_with_tracker(t, tracker),
+            Err(e) => GetSize::get_size_report_with_tracker(e, tracker),
+        };
+        report.absorb_inline(value);
+
+        (report, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        match self {
+            Ok(t) => GetSize::try_get_heap_size_with_tracker(t, tracker),
+            Err(e) => GetSize::try_get_heap_size_with_tracker(e, tracker),
+        }
+    }
 }
 
-impl<T> GetSize for Mutex<T>
-where
-    T: GetSize,
-{
-    fn get_heap_size(&self) -> usize {
-        // We assume that a `Mutex` holds its data at the stack.
-        GetSize::get_heap_size(&*(self.lock().expect("Mutex is poisoned")))
+/// How the value inside a [`Mutex`] or [`RwLock`] is measured while its lock is held elsewhere.
+///
+/// Poisoned locks are always measured, since a panic while holding the lock does not change the
+/// memory occupied by the value. Use [`StandardTracker::set_lock_policy`] to choose the policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum LockPolicy {
+    /// Waits until the lock can be acquired. This deadlocks if the measuring thread does
+    /// already hold the lock itself.
+    #[default]
+    Block,
+    /// Skips the value if the lock can not be acquired immediately, accounting nothing for it.
+    /// Each skipped value is passed to [`GetSizeTracker::track_skipped_lock`] and counted by
+    /// [`SizeReport::skipped`].
+    Skip,
+    /// Fails the fallible methods like [`GetSize::try_get_heap_size`] with
+    /// [`GetSizeError::Locked`] if the lock can not be acquired immediately. The infallible
+    /// methods skip the value instead, just like [`LockPolicy::Skip`].
+    Fail,
+}
+
+/// Acquires a lock according to the lock policy of the `tracker`, recovering the guard of a
+/// poisoned lock. Returns `None` if the lock is held elsewhere and should not be waited for.
+fn lock_with_policy<G, TR: GetSizeTracker>(
+    tracker: &TR,
+    lock: impl FnOnce() -> LockResult<G>,
+    try_lock: impl FnOnce() -> TryLockResult<G>,
+) -> Option<G> {
+    match tracker.options().lock_policy {
+        LockPolicy::Block => Some(lock().unwrap_or_else(PoisonError::into_inner)),
+        LockPolicy::Skip | LockPolicy::Fail => match try_lock() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        },
     }
 }
 
-impl<T> GetSize for RwLock<T>
+/// Implements [`GetSize`] for a lock, which is acquired by the given `lock` and `try_lock`
+/// methods.
+macro_rules! impl_size_lock {
+    ($ty:ident, $lock:ident, $try_lock:ident) => {
+        impl<T> GetSize for $ty<T>
+        where
+            T: GetSize,
+        {
+            fn get_heap_size(&self) -> usize {
+                // We assume that a lock holds its data at the stack.
+                GetSize::get_heap_size(&*(self.$lock().unwrap_or_else(PoisonError::into_inner)))
+            }
+
+            fn get_heap_size_with_tracker<TR: GetSizeTracker>(
+                &self,
+                mut tracker: TR,
+            ) -> (usize, TR) {
+                match lock_with_policy(&tracker, || self.$lock(), || self.$try_lock()) {
+                    Some(value) => GetSize::get_heap_size_with_tracker(&*value, tracker),
+                    None => {
+                        tracker.track_skipped_lock();
+                        (0, tracker)
+                    }
+                }
+            }
+
+            fn get_size_report_with_tracker<TR: GetSizeTracker>(
+                &self,
+                mut tracker: TR,
+            ) -> (SizeReport, TR) {
+                let mut report = SizeReport::new("", Self::get_stack_size(), 0);
+
+                match lock_with_policy(&tracker, || self.$lock(), || self.$try_lock()) {
+                    Some(value) => {
+                        let (value, tr) = GetSize::get_size_report_with_tracker(&*value, tracker);
+                        report.absorb_inline(value);
+                        tracker = tr;
+                    }
+                    None => {
+                        tracker.track_skipped_lock();
+                        report.add_skipped(1);
+                    }
+                }
+
+                (report, tracker)
+            }
+
+            fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+                &self,
+                mut tracker: TR,
+            ) -> Result<(usize, TR), GetSizeError> {
+                match lock_with_policy(&tracker, || self.$lock(), || self.$try_lock()) {
+                    Some(value) => GetSize::try_get_heap_size_with_tracker(&*value, tracker),
+                    None if tracker.options().lock_policy == LockPolicy::Fail => {
+                        Err(GetSizeError::locked::<Self>())
+                    }
+                    None => {
+                        tracker.track_skipped_lock();
+                        Ok((0, tracker))
+                    }
+                }
+            }
+        }
+    };
+}
+
+impl_size_lock!(Mutex, lock, try_lock);
+impl_size_lock!(RwLock, read, try_read);
+
+/// Implements [`GetSize`] for a cell which is initialized at most once, measuring its value
+/// only if it has been initialized. Lazy cells are not forced.
+macro_rules! impl_size_once {
+    ($ty:ident $(, $f:ident)?) => {
+        impl<T $(, $f: FnOnce() -> T)?> GetSize for $ty<T $(, $f)?>
+        where
+            T: GetSize,
+        {
+            fn get_heap_size(&self) -> usize {
+                // We assume that the cell holds its data at the stack.
+                match $ty::get(self) {
+                    None => 0,
+                    Some(value) => GetSize::get_heap_size(value),
+                }
+            }
+
+            fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+                match $ty::get(self) {
+                    None => (0, tracker),
+                    Some(value) => GetSize::get_heap_size_with_tracker(value, tracker),
+                }
+            }
+
+            fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+                &self,
+                tracker: TR,
+            ) -> Result<(usize, TR), GetSizeError> {
+                match $ty::get(self) {
+                    None => Ok((0, tracker)),
+                    Some(value) => GetSize::try_get_heap_size_with_tracker(value, tracker),
+                }
+            }
+        }
+    };
+}
+
+impl_size_once!(OnceLock);
+impl_size_once!(OnceCell);
+// `LazyLock::get` and `LazyCell::get` were only stabilized recently, so these can be disabled in
+// order to build the crate with older compilers.
+#[cfg(feature = "lazy-cell")]
+impl_size_once!(LazyLock, F);
+#[cfg(feature = "lazy-cell")]
+impl_size_once!(LazyCell, F);
+
+impl<T> GetSize for Cell<T>
 where
-    T: GetSize,
+    T: GetSize + Copy,
 {
     fn get_heap_size(&self) -> usize {
-        // We assume that a `RwLock` holds its data at the stack.
-        GetSize::get_heap_size(&*(self.read().expect("RwLock is poisoned")))
+        GetSize::get_heap_size(&self.get())
+    }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+        GetSize::get_heap_size_with_tracker(&self.get(), tracker)
     }
 }
 
-impl<T> GetSize for OnceLock<T>
+/// Measures the value inside a [`RefCell`] only while it is not mutably borrowed, since it can
+/// not be inspected otherwise. The infallible methods skip a mutably borrowed value, passing it
+/// to [`GetSizeTracker::track_skipped_lock`], while the fallible methods fail with
+/// [`GetSizeError::Borrowed`].
+impl<T> GetSize for RefCell<T>
 where
     T: GetSize,
 {
     fn get_heap_size(&self) -> usize {
-        // We assume that a `OnceLock` holds its data at the stack.
-        match self.get() {
-            None => 0,
-            Some(value) => GetSize::get_heap_size(&*value),
+        // We assume that a `RefCell` holds its data at the stack.
+        self.try_borrow()
+            .map_or(0, |value| GetSize::get_heap_size(&*value))
+    }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
+        if let Ok(value) = self.try_borrow() {
+            GetSize::get_heap_size_with_tracker(&*value, tracker)
+        } else {
+            tracker.track_skipped_lock();
+            (0, tracker)
+        }
+    }
+
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(
+        &self,
+        mut tracker: TR,
+    ) -> (SizeReport, TR) {
+        let mut report = SizeReport::new("", Self::get_stack_size(), 0);
+
+        if let Ok(value) = self.try_borrow() {
+            let (value, tr) = GetSize::get_size_report_with_tracker(&*value, tracker);
+            report.absorb_inline(value);
+            tracker = tr;
+        } else {
+            tracker.track_skipped_lock();
+            report.add_skipped(1);
         }
+
+        (report, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let value = self
+            .try_borrow()
+            .map_err(|_| GetSizeError::borrowed::<Self>())?;
+
+        GetSize::try_get_heap_size_with_tracker(&*value, tracker)
     }
 }
 
@@ -495,6 +1789,14 @@ impl GetSize for String {
     fn get_heap_size(&self) -> usize {
         self.capacity()
     }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+        let ptr =
+            NonNull::new(self.as_ptr().cast_mut()).expect("The buffer of a String is never null");
+        let total = allocation_size_of(&tracker, ptr, array_layout::<u8>(self.capacity()));
+
+        (total, tracker)
+    }
 }
 
 impl GetSize for &str {}
@@ -503,6 +1805,12 @@ impl GetSize for std::ffi::CString {
     fn get_heap_size(&self) -> usize {
         self.as_bytes_with_nul().len()
     }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+        let total = allocation_size(&tracker, array_layout::<u8>(self.as_bytes_with_nul().len()));
+
+        (total, tracker)
+    }
 }
 
 impl GetSize for &std::ffi::CStr {
@@ -515,6 +1823,12 @@ impl GetSize for std::ffi::OsString {
     fn get_heap_size(&self) -> usize {
         self.len()
     }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+        let total = allocation_size(&tracker, array_layout::<u8>(self.len()));
+
+        (total, tracker)
+    }
 }
 
 impl GetSize for &std::ffi::OsStr {
@@ -543,6 +1857,24 @@ where
 
         total
     }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+        let (mut total, tracker) = GetSize::get_heap_size_with_tracker(self.get_ref(), tracker);
+
+        total += allocation_size(&tracker, array_layout::<u8>(self.capacity()));
+
+        (total, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let (total, tracker) = GetSize::try_get_heap_size_with_tracker(self.get_ref(), tracker)?;
+        let buffer = allocation_size(&tracker, array_layout::<u8>(self.capacity()));
+
+        Ok((try_add::<Self>(total, buffer)?, tracker))
+    }
 }
 
 impl<T> GetSize for std::io::BufWriter<T>
@@ -556,30 +1888,39 @@ where
 
         total
     }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+        let (mut total, tracker) = GetSize::get_heap_size_with_tracker(self.get_ref(), tracker);
+
+        total += allocation_size(&tracker, array_layout::<u8>(self.capacity()));
+
+        (total, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let (total, tracker) = GetSize::try_get_heap_size_with_tracker(self.get_ref(), tracker)?;
+        let buffer = allocation_size(&tracker, array_layout::<u8>(self.capacity()));
+
+        Ok((try_add::<Self>(total, buffer)?, tracker))
+    }
 }
 
 impl GetSize for std::path::PathBuf {
     fn get_heap_size(&self) -> usize {
         self.capacity()
     }
-}
 
-impl GetSize for &std::path::Path {}
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+        let total = allocation_size(&tracker, array_layout::<u8>(self.capacity()));
 
-impl<T> GetSize for Box<[T]>
-where
-    T: GetSize,
-{
-    fn get_heap_size(&self) -> usize {
-        self.iter().map(GetSize::get_size).sum()
+        (total, tracker)
     }
 }
 
-impl GetSize for Box<str> {
-    fn get_heap_size(&self) -> usize {
-        self.len()
-    }
-}
+impl GetSize for &std::path::Path {}
 
 #[cfg(feature = "chrono")]
 mod chrono {
@@ -612,20 +1953,40 @@ impl GetSize for url::Url {
     }
 }
 
+/// Only a [`Bytes`](bytes::Bytes) which is the unique owner of its buffer accounts for it, which
+/// matches the allocation for buffers created from a slice, a boxed slice, or a [`Vec`] or
+/// [`String`] without spare capacity.
+///
+/// The API of `bytes` exposes neither the capacity of a buffer nor which views share it. The spare
+/// capacity of a [`Vec`] converted into a [`Bytes`](bytes::Bytes) is therefore not accounted for,
+/// and neither are buffers shared by multiple views. Static buffers are not allocated at all.
 #[cfg(feature = "bytes")]
 impl GetSize for bytes::Bytes {
     fn get_heap_size(&self) -> usize {
-        self.len()
+        if self.is_unique() { self.len() } else { 0 }
     }
 }
 
 #[cfg(feature = "bytes")]
 impl GetSize for bytes::BytesMut {
     fn get_heap_size(&self) -> usize {
-        self.len()
+        self.capacity()
     }
 }
 
+/// Determines how many bytes the allocator reserves for a hashbrown table of the given
+/// `allocation_size`, which stores its elements of type `T`.
+#[cfg(feature = "hashbrown")]
+fn hashbrown_table_size<T, TR: GetSizeTracker>(allocation_size: usize, tracker: &TR) -> usize {
+    let table = Layout::from_size_align(
+        allocation_size,
+        std::mem::align_of::<T>().max(HASH_TABLE_GROUP_WIDTH),
+    )
+    .expect("The table does already exist in memory");
+
+    crate::allocation_size(tracker, table)
+}
+
 #[cfg(feature = "hashbrown")]
 impl<K, V, H> GetSize for hashbrown::HashMap<K, V, H>
 where
@@ -634,11 +1995,34 @@ where
     H: std::hash::BuildHasher,
 {
     fn get_heap_size(&self) -> usize {
-        self.allocation_size()
-            + self
-                .iter()
-                .map(|(k, v)| k.get_heap_size() + v.get_heap_size())
-                .sum::<usize>()
+        let tracker = StandardTracker::default();
+
+        let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
+        total
+    }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
+        let mut total = hashbrown_table_size::<(K, V), _>(self.allocation_size(), &tracker);
+
+        for (k, v) in self {
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(k, tracker);
+            total += total_add;
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(v, tr);
+            total += total_add;
+            tracker = tr;
+        }
+
+        (total, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let table = hashbrown_table_size::<(K, V), _>(self.allocation_size(), &tracker);
+
+        __macro_support::try_entry_buffer_heap_size_with_tracker(self, Some(table), tracker)
     }
 }
 
@@ -649,7 +2033,32 @@ where
     H: std::hash::BuildHasher,
 {
     fn get_heap_size(&self) -> usize {
-        self.allocation_size() + self.iter().map(GetSize::get_heap_size).sum::<usize>()
+        let tracker = StandardTracker::default();
+
+        let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
+        total
+    }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
+        let mut total = hashbrown_table_size::<T, _>(self.allocation_size(), &tracker);
+
+        for v in self {
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(v, tracker);
+            total += total_add;
+            tracker = tr;
+        }
+
+        (total, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let table = hashbrown_table_size::<T, _>(self.allocation_size(), &tracker);
+
+        __macro_support::try_buffer_heap_size_with_tracker(self, Some(table), tracker)
     }
 }
 
@@ -659,7 +2068,32 @@ where
     T: GetSize,
 {
     fn get_heap_size(&self) -> usize {
-        self.allocation_size() + self.iter().map(GetSize::get_heap_size).sum::<usize>()
+        let tracker = StandardTracker::default();
+
+        let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
+        total
+    }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, mut tracker: TR) -> (usize, TR) {
+        let mut total = hashbrown_table_size::<T, _>(self.allocation_size(), &tracker);
+
+        for v in self {
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(v, tracker);
+            total += total_add;
+            tracker = tr;
+        }
+
+        (total, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let table = hashbrown_table_size::<T, _>(self.allocation_size(), &tracker);
+
+        __macro_support::try_buffer_heap_size_with_tracker(self, Some(table), tracker)
     }
 }
 
@@ -669,14 +2103,46 @@ where
     A::Item: GetSize,
 {
     fn get_heap_size(&self) -> usize {
-        if self.len() <= self.inline_size() {
-            return self.iter().map(GetSize::get_heap_size).sum();
-        }
+        let tracker = StandardTracker::default();
+
+        let (total, _) = GetSize::get_heap_size_with_tracker(self, tracker);
+
+        total
+    }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+        let buffer = smallvec_buffer_size(self, &tracker);
+
+        __macro_support::buffer_heap_size_with_tracker(self, Some(buffer), tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        let buffer = smallvec_buffer_size(self, &tracker);
 
-        self.iter().map(GetSize::get_size).sum()
+        __macro_support::try_buffer_heap_size_with_tracker(self, Some(buffer), tracker)
     }
 }
 
+/// Determines how many bytes the allocator reserves for the buffer of the given `vec`, which is
+/// only allocated once it has spilled onto the heap.
+#[cfg(feature = "smallvec")]
+fn smallvec_buffer_size<A, TR>(vec: &smallvec::SmallVec<A>, tracker: &TR) -> usize
+where
+    A: smallvec::Array,
+    TR: GetSizeTracker,
+{
+    if !vec.spilled() {
+        return 0;
+    }
+
+    let ptr = NonNull::new(vec.as_ptr().cast_mut()).expect("A spilled buffer is never null");
+
+    allocation_size_of(tracker, ptr, array_layout::<A::Item>(vec.capacity()))
+}
+
 #[cfg(feature = "compact-str")]
 impl GetSize for compact_str::CompactString {
     fn get_heap_size(&self) -> usize {
@@ -0,0 +1,500 @@
+/// Implements [`GetSize`](crate::GetSize) for types which can not derive it, like the types of
+/// other crates or hand written collections.
+///
+/// Each invocation implements the trait for a single type, using one of the following
+/// strategies:
+///
+/// - `fields`: sums up the heap sizes of the listed (public) fields.
+/// - `collection`: a collection which iterates over references to its elements and reports its
+///   `capacity`, storing its elements inside a single buffer like a [`Vec`].
+/// - `collection_without_capacity`: a collection which iterates over references to its
+///   elements, but does not reserve any capacity.
+/// - `map`: a map which iterates over pairs of references to its keys and values and reports its
+///   `capacity`, storing its entries inside a single buffer of key value pairs.
+/// - `map_without_capacity`: a map which iterates over pairs of references to its keys and
+///   values, but does not reserve any capacity.
+///
+/// The collections and maps are iterated through `IntoIterator for &Type`, so no `iter` method is
+/// required. Their elements are assumed to be stored inside the heap, so both their stack and
+/// heap sizes are accounted for. All implementations pass the tracker on to the fields or
+/// elements.
+///
+/// The generic parameters are listed without bounds, which instead belong into the `where`
+/// clause.
+///
+/// # Examples
+/// ```
+/// use get_size2::{GetSize, impl_get_size};
+///
+/// # mod other_crate {
+/// #     pub struct Point<T> { pub x: T, pub y: T }
+/// #
+/// #     pub struct SmallMap<K, V> { pub entries: Vec<(K, V)> }
+/// #
+/// #     impl<K, V> SmallMap<K, V> {
+/// #         pub fn capacity(&self) -> usize { self.entries.capacity() }
+/// #     }
+/// #
+/// #     impl<'a, K, V> IntoIterator for &'a SmallMap<K, V> {
+/// #         type Item = (&'a K, &'a V);
+/// #         type IntoIter = std::iter::Map<std::slice::Iter<'a, (K, V)>, fn(&'a (K, V)) -> (&'a K, &'a V)>;
+/// #
+/// #         fn into_iter(self) -> Self::IntoIter {
+/// #             self.entries.iter().map(|(k, v)| (k, v))
+/// #         }
+/// #     }
+/// # }
+/// use other_crate::{Point, SmallMap};
+///
+/// impl_get_size!(impl<T> fields for Point<T> { x, y } where T: GetSize);
+/// impl_get_size!(impl<K, V> map for SmallMap<K, V> where K: GetSize, V: GetSize);
+///
+/// let point = Point { x: String::from("a"), y: String::from("bc") };
+/// assert_eq!(point.get_heap_size(), 3);
+///
+/// let mut map = SmallMap { entries: Vec::with_capacity(4) };
+/// map.entries.push((1u64, String::from("Hello")));
+/// assert_eq!(map.get_heap_size(), 4 * (size_of::<u64>() + size_of::<String>()) + 5);
+/// ```
+#[macro_export]
+macro_rules! impl_get_size {
+    (impl $(<$($generic:tt),* $(,)?>)? fields for $ty:ty { $($field:tt),+ $(,)? } $(where $($bound:tt)+)?) => {
+        impl $(<$($generic),*>)? $crate::GetSize for $ty $(where $($bound)+)? {
+            fn get_heap_size(&self) -> usize {
+                let tracker = $crate::StandardTracker::default();
+
+                let (total, _) = $crate::GetSize::get_heap_size_with_tracker(self, tracker);
+
+                total
+            }
+
+            fn get_heap_size_with_tracker<TR: $crate::GetSizeTracker>(
+                &self,
+                tracker: TR,
+            ) -> (usize, TR) {
+                let mut total = 0;
+
+                $(
+                    let (total_add, tracker) =
+                        $crate::GetSize::get_heap_size_with_tracker(&self.$field, tracker);
+                    total += total_add;
+                )+
+
+                (total, tracker)
+            }
+
+            fn get_size_report_with_tracker<TR: $crate::GetSizeTracker>(
+                &self,
+                tracker: TR,
+            ) -> ($crate::SizeReport, TR) {
+                let mut report = $crate::SizeReport::new(
+                    "",
+                    <Self as $crate::GetSize>::get_stack_size(),
+                    0,
+                );
+
+                $(
+                    let (child, tracker) =
+                        $crate::GetSize::get_size_report_with_tracker(&self.$field, tracker);
+                    report.add_inline_child(child.named(stringify!($field)));
+                )+
+
+                (report, tracker)
+            }
+
+            fn try_get_heap_size_with_tracker<TR: $crate::GetSizeTracker>(
+                &self,
+                tracker: TR,
+            ) -> ::core::result::Result<(usize, TR), $crate::GetSizeError> {
+                let mut total: usize = 0;
+
+                $(
+                    let (total_add, tracker) =
+                        $crate::GetSize::try_get_heap_size_with_tracker(&self.$field, tracker)?;
+                    total = total
+                        .checked_add(total_add)
+                        .ok_or_else($crate::GetSizeError::overflow::<Self>)?;
+                )+
+
+                ::core::result::Result::Ok((total, tracker))
+            }
+        }
+    };
+    (impl $(<$($generic:tt),* $(,)?>)? collection for $ty:ty $(where $($bound:tt)+)?) => {
+        $crate::impl_get_size!(@elements with_capacity [$($($generic),*)?] [$ty] [$($($bound)+)?]);
+    };
+    (impl $(<$($generic:tt),* $(,)?>)? collection_without_capacity for $ty:ty $(where $($bound:tt)+)?) => {
+        $crate::impl_get_size!(@elements without_capacity [$($($generic),*)?] [$ty] [$($($bound)+)?]);
+    };
+    (impl $(<$($generic:tt),* $(,)?>)? map for $ty:ty $(where $($bound:tt)+)?) => {
+        $crate::impl_get_size!(@entries with_capacity [$($($generic),*)?] [$ty] [$($($bound)+)?]);
+    };
+    (impl $(<$($generic:tt),* $(,)?>)? map_without_capacity for $ty:ty $(where $($bound:tt)+)?) => {
+        $crate::impl_get_size!(@entries without_capacity [$($($generic),*)?] [$ty] [$($($bound)+)?]);
+    };
+    (@elements $capacity:ident [$($generic:tt),*] [$ty:ty] [$($bound:tt)*]) => {
+        impl<$($generic),*> $crate::GetSize for $ty where $($bound)* {
+            fn get_heap_size(&self) -> usize {
+                let tracker = $crate::StandardTracker::default();
+
+                let (total, _) = $crate::GetSize::get_heap_size_with_tracker(self, tracker);
+
+                total
+            }
+
+            fn get_heap_size_with_tracker<TR: $crate::GetSizeTracker>(
+                &self,
+                tracker: TR,
+            ) -> (usize, TR) {
+                $crate::__macro_support::elements_heap_size_with_tracker(
+                    self,
+                    $crate::impl_get_size!(@capacity $capacity self),
+                    tracker,
+                )
+            }
+
+            fn get_size_report_with_tracker<TR: $crate::GetSizeTracker>(
+                &self,
+                tracker: TR,
+            ) -> ($crate::SizeReport, TR) {
+                $crate::__macro_support::elements_size_report_with_tracker(
+                    <Self as $crate::GetSize>::get_stack_size(),
+                    self,
+                    $crate::impl_get_size!(@capacity $capacity self),
+                    tracker,
+                )
+            }
+
+            fn try_get_heap_size_with_tracker<TR: $crate::GetSizeTracker>(
+                &self,
+                tracker: TR,
+            ) -> ::core::result::Result<(usize, TR), $crate::GetSizeError> {
+                $crate::__macro_support::try_elements_heap_size_with_tracker(
+                    self,
+                    $crate::impl_get_size!(@capacity $capacity self),
+                    tracker,
+                )
+            }
+        }
+    };
+    (@entries $capacity:ident [$($generic:tt),*] [$ty:ty] [$($bound:tt)*]) => {
+        impl<$($generic),*> $crate::GetSize for $ty where $($bound)* {
+            fn get_heap_size(&self) -> usize {
+                let tracker = $crate::StandardTracker::default();
+
+                let (total, _) = $crate::GetSize::get_heap_size_with_tracker(self, tracker);
+
+                total
+            }
+
+            fn get_heap_size_with_tracker<TR: $crate::GetSizeTracker>(
+                &self,
+                tracker: TR,
+            ) -> (usize, TR) {
+                $crate::__macro_support::entries_heap_size_with_tracker(
+                    self,
+                    $crate::impl_get_size!(@capacity $capacity self),
+                    tracker,
+                )
+            }
+
+            fn get_size_report_with_tracker<TR: $crate::GetSizeTracker>(
+                &self,
+                tracker: TR,
+            ) -> ($crate::SizeReport, TR) {
+                $crate::__macro_support::entries_size_report_with_tracker(
+                    <Self as $crate::GetSize>::get_stack_size(),
+                    self,
+                    $crate::impl_get_size!(@capacity $capacity self),
+                    tracker,
+                )
+            }
+
+            fn try_get_heap_size_with_tracker<TR: $crate::GetSizeTracker>(
+                &self,
+                tracker: TR,
+            ) -> ::core::result::Result<(usize, TR), $crate::GetSizeError> {
+                $crate::__macro_support::try_entries_heap_size_with_tracker(
+                    self,
+                    $crate::impl_get_size!(@capacity $capacity self),
+                    tracker,
+                )
+            }
+        }
+    };
+    // `self` is handed over explicitly, since it can not be named outside of the method.
+    (@capacity with_capacity $self:tt) => {
+        ::core::option::Option::Some($self.capacity())
+    };
+    (@capacity without_capacity $self:tt) => {
+        ::core::option::Option::None
+    };
+}
+
+/// The functions behind [`impl_get_size!`], which infer the element types from the iterators.
+#[doc(hidden)]
+pub mod __macro_support {
+    use crate::{
+        GetSize, GetSizeError, GetSizeTracker, SizeReport, allocation_size, array_layout,
+        get_elements_report, get_entries_report, try_add,
+    };
+
+    pub fn elements_heap_size_with_tracker<'a, T, I, TR>(
+        elements: I,
+        capacity: Option<usize>,
+        tracker: TR,
+    ) -> (usize, TR)
+    where
+        T: GetSize + 'a,
+        I: IntoIterator<Item = &'a T>,
+        TR: GetSizeTracker,
+    {
+        let buffer =
+            capacity.map(|capacity| allocation_size(&tracker, array_layout::<T>(capacity)));
+
+        buffer_heap_size_with_tracker(elements, buffer, tracker)
+    }
+
+    pub fn elements_size_report_with_tracker<'a, T, I, TR>(
+        stack_size: usize,
+        elements: I,
+        capacity: Option<usize>,
+        tracker: TR,
+    ) -> (SizeReport, TR)
+    where
+        T: GetSize + 'a,
+        I: IntoIterator<Item = &'a T>,
+        TR: GetSizeTracker,
+    {
+        let buffer =
+            capacity.map(|capacity| allocation_size(&tracker, array_layout::<T>(capacity)));
+
+        buffer_size_report_with_tracker(stack_size, elements, buffer, tracker)
+    }
+
+    pub fn try_elements_heap_size_with_tracker<'a, T, I, TR>(
+        elements: I,
+        capacity: Option<usize>,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError>
+    where
+        T: GetSize + 'a,
+        I: IntoIterator<Item = &'a T>,
+        TR: GetSizeTracker,
+    {
+        let buffer =
+            capacity.map(|capacity| allocation_size(&tracker, array_layout::<T>(capacity)));
+
+        try_buffer_heap_size_with_tracker(elements, buffer, tracker)
+    }
+
+    /// Sums up the heap sizes of the `elements`, which are stored inside a `buffer` of the given
+    /// allocation size, or each inside their own allocation if there is no `buffer`.
+    pub(crate) fn buffer_heap_size_with_tracker<'a, T, I, TR>(
+        elements: I,
+        buffer: Option<usize>,
+        mut tracker: TR,
+    ) -> (usize, TR)
+    where
+        T: GetSize + 'a,
+        I: IntoIterator<Item = &'a T>,
+        TR: GetSizeTracker,
+    {
+        let Some(mut total) = buffer else {
+            let mut total = 0;
+
+            for element in elements {
+                // We assume that the elements are hold inside the heap.
+                let (total_add, tr) = GetSize::get_size_with_tracker(element, tracker);
+                total += total_add;
+                tracker = tr;
+            }
+
+            return (total, tracker);
+        };
+
+        for element in elements {
+            // The elements are stored inside the buffer itself.
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(element, tracker);
+            total += total_add;
+            tracker = tr;
+        }
+
+        (total, tracker)
+    }
+
+    /// The fallible counterpart of [`buffer_heap_size_with_tracker`].
+    pub(crate) fn try_buffer_heap_size_with_tracker<'a, T, I, TR>(
+        elements: I,
+        buffer: Option<usize>,
+        mut tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError>
+    where
+        T: GetSize + 'a,
+        I: IntoIterator<Item = &'a T>,
+        TR: GetSizeTracker,
+    {
+        let Some(mut total) = buffer else {
+            let mut total = 0;
+
+            for element in elements {
+                let (total_add, tr) = GetSize::try_get_size_with_tracker(element, tracker)?;
+                total = try_add::<[T]>(total, total_add)?;
+                tracker = tr;
+            }
+
+            return Ok((total, tracker));
+        };
+
+        for element in elements {
+            let (total_add, tr) = GetSize::try_get_heap_size_with_tracker(element, tracker)?;
+            total = try_add::<[T]>(total, total_add)?;
+            tracker = tr;
+        }
+
+        Ok((total, tracker))
+    }
+
+    /// The report counterpart of [`buffer_heap_size_with_tracker`].
+    pub(crate) fn buffer_size_report_with_tracker<'a, T, I, TR>(
+        stack_size: usize,
+        elements: I,
+        buffer: Option<usize>,
+        tracker: TR,
+    ) -> (SizeReport, TR)
+    where
+        T: GetSize + 'a,
+        I: IntoIterator<Item = &'a T>,
+        TR: GetSizeTracker,
+    {
+        let (elements, tracker) = get_elements_report(elements, tracker);
+
+        // The buffer holds the elements, which do already account for their own stack size.
+        let heap_size = buffer.map_or(0, |buffer| buffer - elements.stack());
+        let mut report = SizeReport::new("", stack_size, heap_size);
+        report.add_heap_child(elements);
+
+        (report, tracker)
+    }
+
+    pub fn entries_heap_size_with_tracker<'a, K, V, I, TR>(
+        entries: I,
+        capacity: Option<usize>,
+        mut tracker: TR,
+    ) -> (usize, TR)
+    where
+        K: GetSize + 'a,
+        V: GetSize + 'a,
+        I: IntoIterator<Item = (&'a K, &'a V)>,
+        TR: GetSizeTracker,
+    {
+        let Some(capacity) = capacity else {
+            let mut total = 0;
+
+            for (k, v) in entries {
+                let (total_add, tr) = GetSize::get_size_with_tracker(k, tracker);
+                total += total_add;
+                let (total_add, tr) = GetSize::get_size_with_tracker(v, tr);
+                total += total_add;
+                tracker = tr;
+            }
+
+            return (total, tracker);
+        };
+
+        let mut total = allocation_size(&tracker, array_layout::<(K, V)>(capacity));
+
+        for (k, v) in entries {
+            // The keys and values are stored inside the buffer itself.
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(k, tracker);
+            total += total_add;
+            let (total_add, tr) = GetSize::get_heap_size_with_tracker(v, tr);
+            total += total_add;
+            tracker = tr;
+        }
+
+        (total, tracker)
+    }
+
+    pub fn try_entries_heap_size_with_tracker<'a, K, V, I, TR>(
+        entries: I,
+        capacity: Option<usize>,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError>
+    where
+        K: GetSize + 'a,
+        V: GetSize + 'a,
+        I: IntoIterator<Item = (&'a K, &'a V)>,
+        TR: GetSizeTracker,
+    {
+        let buffer =
+            capacity.map(|capacity| allocation_size(&tracker, array_layout::<(K, V)>(capacity)));
+
+        try_entry_buffer_heap_size_with_tracker(entries, buffer, tracker)
+    }
+
+    /// Sums up the heap sizes of the `entries`, which are stored inside a `buffer` of the given
+    /// allocation size, or each inside their own allocation if there is no `buffer`. Fails if
+    /// any of them fails to be measured.
+    pub(crate) fn try_entry_buffer_heap_size_with_tracker<'a, K, V, I, TR>(
+        entries: I,
+        buffer: Option<usize>,
+        mut tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError>
+    where
+        K: GetSize + 'a,
+        V: GetSize + 'a,
+        I: IntoIterator<Item = (&'a K, &'a V)>,
+        TR: GetSizeTracker,
+    {
+        let Some(mut total) = buffer else {
+            let mut total = 0;
+
+            for (k, v) in entries {
+                let (total_add, tr) = GetSize::try_get_size_with_tracker(k, tracker)?;
+                total = try_add::<[(K, V)]>(total, total_add)?;
+                let (total_add, tr) = GetSize::try_get_size_with_tracker(v, tr)?;
+                total = try_add::<[(K, V)]>(total, total_add)?;
+                tracker = tr;
+            }
+
+            return Ok((total, tracker));
+        };
+
+        for (k, v) in entries {
+            let (total_add, tr) = GetSize::try_get_heap_size_with_tracker(k, tracker)?;
+            total = try_add::<[(K, V)]>(total, total_add)?;
+            let (total_add, tr) = GetSize::try_get_heap_size_with_tracker(v, tr)?;
+            total = try_add::<[(K, V)]>(total, total_add)?;
+            tracker = tr;
+        }
+
+        Ok((total, tracker))
+    }
+
+    pub fn entries_size_report_with_tracker<'a, K, V, I, TR>(
+        stack_size: usize,
+        entries: I,
+        capacity: Option<usize>,
+        tracker: TR,
+    ) -> (SizeReport, TR)
+    where
+        K: GetSize + 'a,
+        V: GetSize + 'a,
+        I: IntoIterator<Item = (&'a K, &'a V)>,
+        TR: GetSizeTracker,
+    {
+        let buffer =
+            capacity.map(|capacity| allocation_size(&tracker, array_layout::<(K, V)>(capacity)));
+
+        let (keys, values, tracker) = get_entries_report(entries, tracker);
+
+        // The buffer holds the entries, which do already account for their own stack size.
+        let heap_size = buffer.map_or(0, |buffer| buffer - keys.stack() - values.stack());
+        let mut report = SizeReport::new("", stack_size, heap_size);
+        report.add_heap_child(keys);
+        report.add_heap_child(values);
+
+        (report, tracker)
+    }
+}
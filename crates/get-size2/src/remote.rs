@@ -0,0 +1,131 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::{GetSize, GetSizeError, GetSizeTracker, SizeReport, StandardTracker};
+
+/// Determines the size of a foreign type `T`, which can not implement [`GetSize`] itself.
+///
+/// This trait is implemented by the mirror definitions generated with
+/// `#[get_size(remote = "...")]`, and used by the [`Remote`] wrapper to implement [`GetSize`].
+pub trait GetSizeRemote<T> {
+    /// Determines how many bytes `value` occupies inside the heap while using a `tracker`.
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(value: &T, tracker: TR) -> (usize, TR);
+
+    /// Determines how many bytes `value` occupies inside the heap.
+    ///
+    /// The default implementation uses a fresh [`StandardTracker`].
+    fn get_heap_size(value: &T) -> usize {
+        let tracker = StandardTracker::default();
+
+        let (total, _) = Self::get_heap_size_with_tracker(value, tracker);
+
+        total
+    }
+
+    /// Creates a [`SizeReport`] for `value` while using a `tracker`.
+    ///
+    /// The default implementation returns a single node without any children.
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(
+        value: &T,
+        tracker: TR,
+    ) -> (SizeReport, TR) {
+        let (heap_size, tracker) = Self::get_heap_size_with_tracker(value, tracker);
+
+        (
+            SizeReport::new("", std::mem::size_of::<T>(), heap_size),
+            tracker,
+        )
+    }
+
+    /// Determines how many bytes `value` occupies inside the heap while using a `tracker`,
+    /// failing instead of skipping values which can not be inspected.
+    ///
+    /// The default implementation never fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value inside `value` can not be inspected or if the size overflows.
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        value: &T,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        Ok(Self::get_heap_size_with_tracker(value, tracker))
+    }
+}
+
+/// A transparent wrapper around a foreign `T`, which implements [`GetSize`] using the mirror
+/// definition `D`.
+#[repr(transparent)]
+pub struct Remote<T, D> {
+    value: T,
+    definition: PhantomData<fn() -> D>,
+}
+
+impl<T, D> Remote<T, D> {
+    /// Wraps the given `value`.
+    pub const fn new(value: T) -> Self {
+        Self {
+            value,
+            definition: PhantomData,
+        }
+    }
+
+    /// Consumes the wrapper, returning the wrapped value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, D> From<T> for Remote<T, D> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T, D> Deref for Remote<T, D> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T, D> DerefMut for Remote<T, D> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: Clone, D> Clone for Remote<T, D> {
+    fn clone(&self) -> Self {
+        Self::new(self.value.clone())
+    }
+}
+
+impl<T: fmt::Debug, D> fmt::Debug for Remote<T, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T, D: GetSizeRemote<T>> GetSize for Remote<T, D> {
+    fn get_heap_size(&self) -> usize {
+        D::get_heap_size(&self.value)
+    }
+
+    fn get_heap_size_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (usize, TR) {
+        D::get_heap_size_with_tracker(&self.value, tracker)
+    }
+
+    fn get_size_report_with_tracker<TR: GetSizeTracker>(&self, tracker: TR) -> (SizeReport, TR) {
+        D::get_size_report_with_tracker(&self.value, tracker)
+    }
+
+    fn try_get_heap_size_with_tracker<TR: GetSizeTracker>(
+        &self,
+        tracker: TR,
+    ) -> Result<(usize, TR), GetSizeError> {
+        D::try_get_heap_size_with_tracker(&self.value, tracker)
+    }
+}
@@ -0,0 +1,284 @@
+use std::fmt;
+
+/// A tree of named nodes, breaking down the size of an object into the sizes of its parts.
+///
+/// Every node accounts for the bytes occupied by the value(s) it describes, including the bytes
+/// of all of its children. The `stack` bytes are the bytes occupied by the value(s) themselves,
+/// the `heap` bytes are the bytes exclusively owned inside the heap and the `shared_heap` bytes
+/// are the bytes reachable through shared ownership, like an [`Arc`](std::sync::Arc).
+///
+/// Collections merge the reports of all of their elements into a single child named `[*]`,
+/// maps do the same for their keys and values using the names `[key]` and `[value]`.
+///
+/// Values which have not been measured, like the contents of a [`Mutex`](std::sync::Mutex)
+/// locked elsewhere while using [`LockPolicy::Skip`](crate::LockPolicy::Skip), are counted as
+/// `skipped` by the node describing them and all of its ancestors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SizeReport {
+    name: String,
+    stack: usize,
+    heap: usize,
+    shared_heap: usize,
+    skipped: usize,
+    children: Vec<SizeReport>,
+}
+
+impl SizeReport {
+    /// Creates a new report node without any children.
+    #[must_use]
+    pub fn new(name: impl Into<String>, stack: usize, heap: usize) -> Self {
+        Self {
+            name: name.into(),
+            stack,
+            heap,
+            shared_heap: 0,
+            skipped: 0,
+            children: Vec::new(),
+        }
+    }
+
+    /// Changes the name of this node.
+    #[must_use]
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Get the name of this node.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the number of bytes occupied by the value(s) this node describes.
+    #[must_use]
+    pub const fn stack(&self) -> usize {
+        self.stack
+    }
+
+    /// Get the number of bytes exclusively owned inside the heap.
+    #[must_use]
+    pub const fn heap(&self) -> usize {
+        self.heap
+    }
+
+    /// Get the number of bytes inside the heap which are reachable through shared ownership.
+    #[must_use]
+    pub const fn shared_heap(&self) -> usize {
+        self.shared_heap
+    }
+
+    /// Get the number of values inside this node which have not been measured.
+    #[must_use]
+    pub const fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Get the total number of bytes accounted for by this node.
+    #[must_use]
+    pub const fn total(&self) -> usize {
+        self.stack + self.heap + self.shared_heap
+    }
+
+    /// Get the children of this node.
+    #[must_use]
+    pub fn children(&self) -> &[Self] {
+        &self.children
+    }
+
+    /// Consumes this node, returning its children.
+    #[must_use]
+    pub fn into_children(self) -> Vec<Self> {
+        self.children
+    }
+
+    /// Adds the given number of bytes to the exclusively owned heap bytes of this node.
+    pub fn add_heap(&mut self, heap: usize) {
+        self.heap += heap;
+    }
+
+    /// Adds the given number of bytes to the shared heap bytes of this node.
+    pub fn add_shared_heap(&mut self, shared_heap: usize) {
+        self.shared_heap += shared_heap;
+    }
+
+    /// Adds the given number of values which have not been measured to this node.
+    pub fn add_skipped(&mut self, skipped: usize) {
+        self.skipped += skipped;
+    }
+
+    /// Adds a `child` describing a value stored inline inside the value(s) of this node, like a
+    /// struct field.
+    ///
+    /// The heap bytes of the child are added to this node, its stack bytes are assumed to be
+    /// already accounted for.
+    pub fn add_inline_child(&mut self, child: Self) {
+        self.heap += child.heap;
+        self.shared_heap += child.shared_heap;
+        self.skipped += child.skipped;
+        self.children.push(child);
+    }
+
+    /// Adds a `child` describing value(s) stored inside a heap allocation exclusively owned by
+    /// the value(s) of this node, like the elements of a [`Vec`].
+    ///
+    /// Both the stack and the heap bytes of the child are added to the heap bytes of this node.
+    pub fn add_heap_child(&mut self, child: Self) {
+        self.heap += child.stack + child.heap;
+        self.shared_heap += child.shared_heap;
+        self.skipped += child.skipped;
+        self.children.push(child);
+    }
+
+    /// Adds the heap bytes and the children of `other` to this node, treating `other` as a
+    /// transparent wrapper stored inline, like the value inside of an [`Option`].
+    pub fn absorb_inline(&mut self, other: Self) {
+        self.heap += other.heap;
+        self.shared_heap += other.shared_heap;
+        self.skipped += other.skipped;
+        self.children.extend(other.children);
+    }
+
+    /// Adds the stack bytes, heap bytes and the children of `other` to this node, treating
+    /// `other` as a transparent value stored inside a heap allocation exclusively owned by this
+    /// node, like the value inside of a [`Box`].
+    pub fn absorb_heap(&mut self, other: Self) {
+        self.heap += other.stack + other.heap;
+        self.shared_heap += other.shared_heap;
+        self.skipped += other.skipped;
+        self.children.extend(other.children);
+    }
+
+    /// Merges the `other` report into this one, adding up the bytes of both and merging their
+    /// children by name.
+    pub fn merge(&mut self, other: Self) {
+        self.stack += other.stack;
+        self.heap += other.heap;
+        self.shared_heap += other.shared_heap;
+        self.skipped += other.skipped;
+
+        for child in other.children {
+            match self.children.iter_mut().find(|c| c.name == child.name) {
+                Some(existing) => existing.merge(child),
+                None => self.children.push(child),
+            }
+        }
+    }
+
+    /// Turns all exclusively owned heap bytes of this node and its children into shared heap
+    /// bytes.
+    #[must_use]
+    pub fn into_shared(mut self) -> Self {
+        self.shared_heap += self.heap;
+        self.heap = 0;
+        self.children = self.children.into_iter().map(Self::into_shared).collect();
+        self
+    }
+
+    /// Finds the node located at the given `path`, which starts with the name of this node.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// report.find("root.index.entries[*].payload");
+    /// ```
+    #[must_use]
+    pub fn find(&self, path: &str) -> Option<&Self> {
+        let mut segments = split_path(path).into_iter();
+
+        if segments.next()? != self.name {
+            return None;
+        }
+
+        let mut node = self;
+        for segment in segments {
+            node = node.children.iter().find(|c| c.name == segment)?;
+        }
+
+        Some(node)
+    }
+
+    /// Calls `f` with the full path of this node and each of its descendants.
+    pub fn for_each<F: FnMut(&str, &Self)>(&self, mut f: F) {
+        self.for_each_inner(&mut String::new(), &mut f);
+    }
+
+    fn for_each_inner<F: FnMut(&str, &Self)>(&self, path: &mut String, f: &mut F) {
+        let len = path.len();
+
+        push_segment(path, &self.name);
+        f(path, self);
+
+        for child in &self.children {
+            child.for_each_inner(path, f);
+        }
+
+        path.truncate(len);
+    }
+}
+
+impl fmt::Display for SizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut result = Ok(());
+
+        self.for_each(|path, node| {
+            if result.is_ok() {
+                result = write!(
+                    f,
+                    "{path}: stack {}, heap {}, shared heap {}",
+                    node.stack, node.heap, node.shared_heap
+                );
+            }
+
+            if result.is_ok() && node.skipped > 0 {
+                result = write!(f, ", skipped {}", node.skipped);
+            }
+
+            if result.is_ok() {
+                result = writeln!(f);
+            }
+        });
+
+        result
+    }
+}
+
+fn push_segment(path: &mut String, segment: &str) {
+    if !path.is_empty() && !segment.starts_with('[') {
+        path.push('.');
+    }
+
+    path.push_str(segment);
+}
+
+fn split_path(path: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+
+    for (i, c) in path.char_indices() {
+        match c {
+            '.' => {
+                if start < i {
+                    segments.push(&path[start..i]);
+                }
+                start = i + 1;
+            }
+            '[' => {
+                if start < i {
+                    segments.push(&path[start..i]);
+                }
+                start = i;
+            }
+            ']' => {
+                segments.push(&path[start..=i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if start < path.len() {
+        segments.push(&path[start..]);
+    }
+
+    segments
+}
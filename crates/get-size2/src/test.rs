@@ -2,8 +2,12 @@
 
 use std::mem::size_of;
 
+use get_size2::testing::{CountingAllocator, assert_heap_size_matches};
 use get_size2::*;
 
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator::system();
+
 #[derive(GetSize)]
 pub struct TestStruct {
     value1: String,
@@ -50,6 +54,12 @@ struct TestStructNoGetSize {
     value: String,
 }
 
+impl TestStructNoGetSize {
+    fn heap_bytes(&self) -> usize {
+        self.value.capacity()
+    }
+}
+
 #[test]
 fn derive_struct_with_generics_and_ignore() {
     let no_impl = TestStructNoGetSize {
@@ -237,6 +247,381 @@ fn derive_enum_c_style() {
     assert_eq!(test.get_heap_size(), 0);
 }
 
+#[derive(GetSize)]
+#[get_size(ignore(C))]
+enum TestEnumHelpers<C> {
+    Tuple(
+        String,
+        #[get_size(ignore)] String,
+        #[get_size(size = 100)] TestStructNoGetSize,
+        #[get_size(size_fn = get_size_helper)] C,
+    ),
+    Named {
+        value1: String,
+        #[get_size(ignore)]
+        value2: String,
+        #[get_size(size = 100)]
+        value3: TestStructNoGetSize,
+        #[get_size(size_fn = get_size_helper)]
+        value4: C,
+    },
+}
+
+#[test]
+fn derive_enum_with_helpers() {
+    let no_impl = || TestStructNoGetSize {
+        value: "World!".into(),
+    };
+
+    let test = TestEnumHelpers::Tuple("Hello".into(), "Ignored".into(), no_impl(), no_impl());
+    assert_eq!(test.get_heap_size(), 5 + 100 + 50);
+
+    let test = TestEnumHelpers::Named {
+        value1: "Hello".into(),
+        value2: "Ignored".into(),
+        value3: no_impl(),
+        value4: no_impl(),
+    };
+    assert_eq!(test.get_heap_size(), 5 + 100 + 50);
+
+    let report = test.get_size_report();
+    assert!(report.find("root.Named.value2").is_none());
+    assert_eq!(report.find("root.Named.value3").unwrap().heap(), 100);
+    assert_eq!(report.find("root.Named.value4").unwrap().heap(), 50);
+}
+
+#[derive(GetSize)]
+struct TestTupleStructHelpers(
+    #[get_size(size = 100)] u64,
+    #[get_size(ignore)] String,
+    String,
+);
+
+#[test]
+fn derive_tuple_struct_with_helpers() {
+    let test = TestTupleStructHelpers(0, "Ignored".into(), "Hello".into());
+    assert_eq!(test.get_heap_size(), 100 + 5);
+}
+
+mod size_helpers {
+    pub(super) fn heap_bytes(value: &super::TestStructNoGetSize) -> usize {
+        value.value.capacity()
+    }
+}
+
+#[derive(GetSize)]
+struct TestSizeFnPaths(
+    #[get_size(size_fn = size_helpers::heap_bytes)] TestStructNoGetSize,
+    #[get_size(size_fn = TestStructNoGetSize::heap_bytes)] TestStructNoGetSize,
+    #[get_size(size_fn = |v: &TestStructNoGetSize| v.value.len() * 2)] TestStructNoGetSize,
+);
+
+#[test]
+fn derive_size_fn_paths_and_closures() {
+    let no_impl = |value: &str| TestStructNoGetSize {
+        value: value.into(),
+    };
+
+    let test = TestSizeFnPaths(no_impl("a"), no_impl("bc"), no_impl("def"));
+    assert_eq!(test.get_heap_size(), 1 + 2 + 6);
+    assert_eq!(test.get_size_report().find("root.2").unwrap().heap(), 6);
+}
+
+fn shared_no_get_size<T: GetSizeTracker>(
+    value: &std::rc::Rc<TestStructNoGetSize>,
+    mut tracker: T,
+) -> (usize, T) {
+    if tracker.track(std::rc::Rc::as_ptr(value), std::rc::Rc::clone(value)) {
+        (value.heap_bytes(), tracker)
+    } else {
+        (0, tracker)
+    }
+}
+
+#[derive(GetSize)]
+struct TestSizeFnWithTracker {
+    #[get_size(size_fn_with_tracker = shared_no_get_size)]
+    value: std::rc::Rc<TestStructNoGetSize>,
+}
+
+#[test]
+fn derive_size_fn_with_tracker() {
+    let value = std::rc::Rc::new(TestStructNoGetSize {
+        value: "Hello".into(),
+    });
+
+    let test = vec![
+        TestSizeFnWithTracker {
+            value: std::rc::Rc::clone(&value),
+        },
+        TestSizeFnWithTracker { value },
+    ];
+    assert_eq!(
+        test.get_heap_size(),
+        2 * size_of::<TestSizeFnWithTracker>() + 5
+    );
+    assert_eq!(
+        test.get_size_report().find("root[*].value").unwrap().heap(),
+        5
+    );
+}
+
+#[derive(GetSize)]
+#[get_size(heap_size_fn = |_: &TestUnion| 64)]
+union TestUnion {
+    small: u64,
+    ptr: usize,
+}
+
+mod ffi_size {
+    use get_size2::GetSizeTracker;
+
+    pub(super) trait FfiBacked {
+        fn allocated(&self) -> usize;
+    }
+
+    pub(super) fn get_heap_size_with_tracker<V: FfiBacked, T: GetSizeTracker>(
+        value: &V,
+        tracker: T,
+    ) -> (usize, T) {
+        (value.allocated(), tracker)
+    }
+}
+
+#[derive(GetSize)]
+#[get_size(with = ffi_size)]
+struct TestFfiHandle<T> {
+    len: usize,
+    marker: std::marker::PhantomData<T>,
+}
+
+impl<T> ffi_size::FfiBacked for TestFfiHandle<T> {
+    fn allocated(&self) -> usize {
+        self.len * size_of::<T>()
+    }
+}
+
+#[test]
+fn derive_delegated() {
+    let test = TestUnion { small: 0 };
+    assert_eq!(test.get_heap_size(), 64);
+    assert_eq!(test.get_size(), size_of::<TestUnion>() + 64);
+
+    let tracker = StandardTracker::default();
+    let (size, _) = test.get_heap_size_with_tracker(tracker);
+    assert_eq!(size, 64);
+    assert_eq!(test.try_get_heap_size(), Ok(64));
+
+    let test = TestFfiHandle::<u32> {
+        len: 4,
+        marker: std::marker::PhantomData,
+    };
+    assert_eq!(test.get_heap_size(), 16);
+    assert_eq!(
+        vec![test].get_size_report().find("root[*]").unwrap().heap(),
+        16
+    );
+}
+
+mod facade {
+    pub(super) mod sizes {
+        pub(crate) use get_size2::*;
+    }
+}
+
+#[derive(GetSize)]
+#[get_size(crate = "self::facade::sizes")]
+struct TestCratePath<T> {
+    value: T,
+}
+
+#[derive(GetSize)]
+#[get_size(crate = "crate::test::facade::sizes")]
+enum TestCratePathEnum {
+    Value(String),
+}
+
+#[test]
+fn derive_crate_path() {
+    let test = TestCratePath {
+        value: String::from("Hello"),
+    };
+    assert_eq!(test.get_heap_size(), 5);
+
+    let test = TestCratePathEnum::Value("Hello".into());
+    assert_eq!(test.get_heap_size(), 5);
+}
+
+trait TestKeyed {
+    type Id;
+}
+
+impl TestKeyed for TestStructNoGetSize {
+    type Id = String;
+}
+
+#[derive(GetSize)]
+struct TestInferredBounds<K: TestKeyed, S: ?Sized + 'static> {
+    id: K::Id,
+    marker: std::marker::PhantomData<K>,
+    shared: std::sync::Arc<S>,
+}
+
+#[derive(GetSize)]
+struct TestRecursiveBounds<T> {
+    value: T,
+    children: Vec<TestRecursiveBounds<T>>,
+}
+
+#[derive(GetSize)]
+#[get_size(bound = "T: GetSize")]
+struct TestContainerBound<T, U> {
+    values: Vec<T>,
+    #[get_size(size_fn = |value: &U| value.as_ref().len(), bound = "U: AsRef<[u8]>")]
+    raw: U,
+}
+
+#[test]
+fn derive_bounds() {
+    let test = TestInferredBounds::<TestStructNoGetSize, str> {
+        id: "Hello".into(),
+        marker: std::marker::PhantomData,
+        shared: std::sync::Arc::from("abc"),
+    };
+    assert_eq!(
+        test.get_heap_size(),
+        5 + std::sync::Arc::<str>::from("abc").get_heap_size()
+    );
+
+    let test = TestRecursiveBounds {
+        value: String::from("a"),
+        children: vec![TestRecursiveBounds {
+            value: String::from("bc"),
+            children: Vec::new(),
+        }],
+    };
+    assert_eq!(
+        test.get_heap_size(),
+        1 + size_of::<TestRecursiveBounds<String>>() + 2
+    );
+
+    let test = TestContainerBound {
+        values: vec![String::from("a")],
+        raw: vec![0u8; 3],
+    };
+    assert_eq!(test.get_heap_size(), size_of::<String>() + 1 + 3);
+}
+
+// Type and lifetime parameters must be used by a field, so only const generics can be left unused.
+#[derive(GetSize)]
+struct TestEmptyUnit<const N: usize>;
+
+#[derive(GetSize)]
+struct TestEmptyBraces<const N: usize, const B: bool> {}
+
+#[derive(GetSize)]
+struct TestEmptyTuple<const N: usize>()
+where
+    [u8; N]: Sized;
+
+#[derive(GetSize)]
+enum TestEmptyEnum<const N: usize> {}
+
+#[test]
+fn derive_empty_generics() {
+    assert_eq!(TestEmptyUnit::<4>.get_size(), 0);
+    assert_eq!(TestEmptyBraces::<4, true> {}.get_size(), 0);
+    assert_eq!(TestEmptyTuple::<4>().get_size(), 0);
+    assert_eq!(TestEmptyEnum::<4>::get_stack_size(), 0);
+}
+
+mod upstream {
+    pub struct Message<T> {
+        pub id: u64,
+        pub body: String,
+        pub attachment: T,
+    }
+
+    pub struct Checksum(pub [u8; 32]);
+
+    pub enum Payload {
+        Text(String),
+        Binary { data: Vec<u8>, checksum: Checksum },
+        Empty,
+    }
+}
+
+#[derive(GetSize)]
+#[get_size(remote = "upstream::Message")]
+struct MessageDef<T> {
+    id: u64,
+    #[get_size(ignore)]
+    body: String,
+    attachment: T,
+}
+
+#[derive(GetSize)]
+#[get_size(remote = "upstream::Payload")]
+enum PayloadDef {
+    Text(String),
+    Binary {
+        data: Vec<u8>,
+        #[get_size(size = 0)]
+        checksum: upstream::Checksum,
+    },
+    Empty,
+}
+
+#[derive(GetSize)]
+struct TestRemoteFields {
+    payload: Remote<upstream::Payload, PayloadDef>,
+    #[get_size(size_fn_with_tracker = MessageDef::get_heap_size_with_tracker)]
+    message: upstream::Message<String>,
+}
+
+#[test]
+fn derive_remote() {
+    let message = upstream::Message {
+        id: 1,
+        body: "Ignored".into(),
+        attachment: String::from("Hello"),
+    };
+    assert_eq!(MessageDef::get_heap_size(&message), 5);
+
+    let payload = upstream::Payload::Binary {
+        data: vec![0; 10],
+        checksum: upstream::Checksum([0; 32]),
+    };
+    assert_eq!(PayloadDef::get_heap_size(&payload), 10);
+    assert_eq!(PayloadDef::get_heap_size(&upstream::Payload::Empty), 0);
+
+    let test = TestRemoteFields {
+        payload: Remote::new(upstream::Payload::Text("abc".into())),
+        message,
+    };
+    assert_eq!(test.get_heap_size(), 3 + 5);
+
+    let report = test.get_size_report();
+    assert_eq!(report.total(), test.get_size());
+    assert_eq!(report.find("root.payload.Text.0").unwrap().heap(), 3);
+
+    // Values which can not be inspected make the fallible measurement fail.
+    let message: Remote<_, MessageDef<_>> = Remote::new(upstream::Message {
+        id: 2,
+        body: String::new(),
+        attachment: std::sync::Mutex::new(String::from("Hello")),
+    });
+    assert_eq!(message.try_get_heap_size(), Ok(5));
+
+    let _guard = message.attachment.lock().unwrap();
+    let mut tracker = StandardTracker::default();
+    tracker.set_lock_policy(LockPolicy::Fail);
+    assert_eq!(
+        message.try_get_heap_size_with_tracker(tracker).err(),
+        Some(GetSizeError::locked::<std::sync::Mutex<String>>())
+    );
+}
+
 #[derive(GetSize)]
 pub struct TestNewType(u64);
 
@@ -297,13 +682,28 @@ fn url() {
 fn bytes() {
     const BYTES_STR: &str = "Hello world";
 
-    let bytes = bytes::Bytes::from(BYTES_STR);
+    let bytes = bytes::Bytes::from(String::from(BYTES_STR));
     assert_eq!(bytes.get_heap_size(), BYTES_STR.len());
 
+    // Views sharing a buffer can not be attributed to any of them.
+    let view = bytes.slice(1..5);
+    assert_eq!(bytes.get_heap_size(), 0);
+    assert_eq!(view.get_heap_size(), 0);
+
+    // The spare capacity of a vector is not visible through the API of `bytes`.
+    let mut vec = Vec::with_capacity(64);
+    vec.extend_from_slice(BYTES_STR.as_bytes());
+    assert_eq!(bytes::Bytes::from(vec).get_heap_size(), BYTES_STR.len());
+
+    // Static buffers are not allocated.
+    let bytes = bytes::Bytes::from_static(BYTES_STR.as_bytes());
+    assert_eq!(bytes.get_heap_size(), 0);
+
     let mut bytes_mut = bytes::BytesMut::from(BYTES_STR);
     assert_eq!(bytes_mut.get_heap_size(), BYTES_STR.len());
+    // Truncating keeps the buffer allocated.
     bytes_mut.truncate(0);
-    assert_eq!(bytes_mut.get_heap_size(), 0);
+    assert_eq!(bytes_mut.get_heap_size(), BYTES_STR.len());
 }
 
 #[test]
@@ -317,9 +717,9 @@ fn hashbrown() {
     let mut map = hashbrown::HashTable::new();
     assert_eq!(map.get_heap_size(), 0);
     map.insert_unique(
-        hasher.hash_one(&VALUE_STR),
+        hasher.hash_one(VALUE_STR),
         String::from(VALUE_STR),
-        |value| hasher.hash_one(&value),
+        |value| hasher.hash_one(value),
     );
     assert!(map.get_heap_size() >= size_of::<String>() + VALUE_STR.len());
 
@@ -334,6 +734,96 @@ fn hashbrown() {
     assert!(set.get_heap_size() >= size_of::<String>() + VALUE_STR.len());
 }
 
+#[test]
+#[cfg(target_pointer_width = "64")]
+fn btree_node_layout() {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    let map: BTreeMap<u64, u8> = BTreeMap::new();
+    assert_eq!(map.get_heap_size(), 0);
+
+    // The expected values have been measured using a counting allocator.
+    for (len, expected) in [
+        (1, 112),
+        (11, 112),
+        (12, 432),
+        (1000, 11_072),
+        (10_000, 109_424),
+    ] {
+        let map: BTreeMap<u64, u8> = (0..len).map(|i| (i, 0)).collect();
+        assert_eq!(map.get_heap_size(), expected);
+    }
+
+    let set: BTreeSet<u64> = (0..1000).collect();
+    assert_eq!(set.get_heap_size(), 10_336);
+
+    let report = set.get_size_report();
+    assert_eq!(report.total(), set.get_size());
+    assert_eq!(
+        report.find("root[*]").unwrap().stack(),
+        1000 * size_of::<u64>()
+    );
+
+    let map: BTreeMap<u64, u8> = (0..10_000).map(|i| (i, 0)).collect();
+    let mut tracker = StandardTracker::default();
+    tracker.set_btree_occupancy(BTreeOccupancy::Minimal);
+    let (total, _) = map.get_heap_size_with_tracker(tracker);
+    assert_eq!(total, 255_440);
+}
+
+#[test]
+#[cfg(target_pointer_width = "64")]
+fn linked_list_node_layout() {
+    use std::collections::LinkedList;
+
+    // The expected values have been measured using a counting allocator.
+    let list: LinkedList<u8> = (0..10).collect();
+    assert_eq!(list.get_heap_size(), 240);
+
+    let list: LinkedList<String> = ["Hello".into(), "world".into()].into();
+    assert_eq!(list.get_heap_size(), 2 * (16 + size_of::<String>()) + 10);
+
+    let report = list.get_size_report();
+    assert_eq!(report.total(), list.get_size());
+    assert_eq!(report.find("root[*]").unwrap().heap(), 10);
+}
+
+#[test]
+fn std_hash_table_layout() {
+    use std::collections::{HashMap, HashSet};
+    use std::hash::RandomState;
+
+    for capacity in [0, 1, 3, 4, 7, 8, 14, 15, 28, 100, 1000] {
+        let map = HashMap::<u64, String>::with_capacity(capacity);
+        let other = hashbrown::HashMap::<u64, String, _>::with_capacity_and_hasher(
+            capacity,
+            RandomState::new(),
+        );
+        assert_eq!(map.get_heap_size(), other.allocation_size());
+
+        let set = HashSet::<u8>::with_capacity(capacity);
+        let other =
+            hashbrown::HashSet::<u8, _>::with_capacity_and_hasher(capacity, RandomState::new());
+        assert_eq!(set.get_heap_size(), other.allocation_size());
+    }
+
+    let mut map = HashMap::new();
+    let mut other = hashbrown::HashMap::with_hasher(RandomState::new());
+    for i in 0..100u32 {
+        map.insert(i, [i; 3]);
+        other.insert(i, [i; 3]);
+        assert_eq!(map.get_heap_size(), other.allocation_size());
+    }
+
+    let set: HashSet<String> = ["Hello".into(), "world".into()].into();
+    let report = set.get_size_report();
+    assert_eq!(report.total(), set.get_size());
+    assert_eq!(
+        report.find("root[*]").unwrap().stack(),
+        2 * size_of::<String>()
+    );
+}
+
 #[test]
 fn smallvec() {
     const ITEM_STR: &str = "Hello world";
@@ -342,10 +832,17 @@ fn smallvec() {
     assert_eq!(vec.get_heap_size(), ITEM_STR.len());
     vec.push(String::new());
 
+    // Once spilled, the whole buffer is allocated.
+    assert_eq!(vec.capacity(), 4);
     assert_eq!(
         vec.get_heap_size(),
-        ITEM_STR.len() + std::mem::size_of::<String>() * 3
+        ITEM_STR.len() + std::mem::size_of::<String>() * 4
     );
+
+    // Shrinking the length keeps the buffer spilled.
+    vec.truncate(1);
+    assert!(vec.spilled(), "The buffer must stay on the heap");
+    assert_eq!(vec.get_heap_size(), std::mem::size_of::<String>() * 4);
 }
 
 #[test]
@@ -359,3 +856,1164 @@ fn compact_str() {
     let value = compact_str::CompactString::from(LONG_STR);
     assert_eq!(value.get_heap_size(), LONG_STR.len());
 }
+
+#[test]
+fn shared_ownership_in_collections() {
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::Arc;
+
+    let shared = Arc::new(String::from("Hello world!"));
+    let shared_size = 2 * size_of::<usize>() + size_of::<String>() + 12;
+
+    let vec = vec![Arc::clone(&shared); 1000];
+    assert_eq!(
+        vec.get_heap_size(),
+        vec.capacity() * size_of::<Arc<String>>() + shared_size
+    );
+
+    let boxed = vec![Some(Arc::clone(&shared)); 10].into_boxed_slice();
+    assert_eq!(
+        boxed.get_heap_size(),
+        10 * size_of::<Option<Arc<String>>>() + shared_size
+    );
+
+    let tuple = (
+        Arc::clone(&shared),
+        [Arc::clone(&shared), Arc::clone(&shared)],
+    );
+    assert_eq!(tuple.get_heap_size(), shared_size);
+
+    let mut map = BTreeMap::new();
+    map.insert(1u64, Arc::clone(&shared));
+    map.insert(2u64, Arc::clone(&shared));
+    let leaf = size_of::<usize>()
+        + 2 * size_of::<u16>()
+        + 11 * (size_of::<u64>() + size_of::<Arc<String>>());
+    assert_eq!(
+        map.get_heap_size(),
+        leaf.next_multiple_of(align_of::<usize>()) + shared_size
+    );
+
+    let mut map = HashMap::with_capacity(2);
+    map.insert(1u64, Box::new(Arc::clone(&shared)));
+    map.insert(2u64, Box::new(Arc::clone(&shared)));
+    assert_eq!(
+        map.get_heap_size(),
+        HashMap::<u64, Box<Arc<String>>>::with_capacity(2).get_heap_size()
+            + 2 * size_of::<Arc<String>>()
+            + shared_size
+    );
+}
+
+#[test]
+fn shared_ownership_across_containers_with_tracker() {
+    use std::sync::Arc;
+
+    let shared = Arc::new(vec![0u8; 100]);
+    let shared_size = 2 * size_of::<usize>() + size_of::<Vec<u8>>() + 100;
+
+    let first = vec![Arc::clone(&shared)];
+    let second: Option<Result<Arc<Vec<u8>>, ()>> = Some(Ok(Arc::clone(&shared)));
+
+    let tracker = StandardTracker::default();
+    let (first_size, tracker) = first.get_heap_size_with_tracker(tracker);
+    let (second_size, _) = second.get_heap_size_with_tracker(tracker);
+
+    assert_eq!(first_size, size_of::<Arc<Vec<u8>>>() + shared_size);
+    assert_eq!(second_size, 0);
+}
+
+trait Plugin: DynGetSize {
+    fn id(&self) -> u64;
+}
+
+#[derive(GetSize)]
+struct NamedPlugin {
+    name: String,
+}
+
+impl Plugin for NamedPlugin {
+    fn id(&self) -> u64 {
+        1
+    }
+}
+
+#[derive(GetSize)]
+struct CounterPlugin {
+    counters: Vec<u64>,
+}
+
+impl Plugin for CounterPlugin {
+    fn id(&self) -> u64 {
+        2
+    }
+}
+
+#[test]
+fn trait_objects() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    let plugins: Vec<Box<dyn Plugin>> = vec![
+        Box::new(NamedPlugin {
+            name: "Hello".into(),
+        }),
+        Box::new(CounterPlugin {
+            counters: vec![1, 2, 3],
+        }),
+    ];
+    assert_eq!(plugins[0].id() + plugins[1].id(), 3);
+    assert_eq!((*plugins[0]).get_dyn_heap_size(), 5);
+    assert_eq!(
+        plugins.get_heap_size(),
+        2 * size_of::<Box<dyn Plugin>>()
+            + size_of::<NamedPlugin>()
+            + 5
+            + size_of::<CounterPlugin>()
+            + 3 * size_of::<u64>()
+    );
+
+    let handler: Arc<dyn DynGetSize + Send + Sync> = Arc::new(String::from("Hello"));
+    let handlers = vec![Arc::clone(&handler), handler];
+    assert_eq!(
+        handlers.get_heap_size(),
+        handlers.capacity() * size_of::<Arc<dyn DynGetSize + Send + Sync>>()
+            + 2 * size_of::<usize>()
+            + size_of::<String>()
+            + 5
+    );
+
+    let handler: Rc<dyn DynGetSize> = Rc::new(vec![0u8; 10]);
+    assert_eq!(
+        handler.get_heap_size(),
+        2 * size_of::<usize>() + size_of::<Vec<u8>>() + 10
+    );
+}
+
+#[test]
+fn unsized_boxes() {
+    let boxed: Box<str> = "Hello".into();
+    assert_eq!(boxed.get_heap_size(), 5);
+
+    let boxed: Box<[String]> = vec![String::from("Hello"), String::new()].into_boxed_slice();
+    assert_eq!(boxed.get_heap_size(), 2 * size_of::<String>() + 5);
+    assert_eq!((*boxed).get_dyn_size(), 2 * size_of::<String>() + 5);
+}
+
+#[test]
+fn shared_unsized() {
+    use std::ffi::{CStr, OsStr};
+    use std::path::Path;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    let counters = 2 * size_of::<usize>();
+
+    let value: Arc<str> = Arc::from("Hello world");
+    assert_eq!(value.get_heap_size(), (counters + 11).next_multiple_of(8));
+    let values = vec![Arc::clone(&value), value];
+    assert_eq!(
+        values.get_heap_size(),
+        values.capacity() * size_of::<Arc<str>>() + (counters + 11).next_multiple_of(8)
+    );
+
+    let value: Rc<str> = Rc::from("Hello");
+    assert_eq!(value.get_heap_size(), (counters + 5).next_multiple_of(8));
+
+    let value: Arc<[String]> = Arc::from(vec![String::from("Hello"), String::new()]);
+    assert_eq!(
+        value.get_heap_size(),
+        counters + 2 * size_of::<String>() + 5
+    );
+
+    let value: Rc<[u8]> = Rc::from(&[1u8, 2, 3][..]);
+    assert_eq!(value.get_heap_size(), (counters + 3).next_multiple_of(8));
+
+    let value: Box<Path> = Path::new("/tmp/file").into();
+    assert_eq!(value.get_heap_size(), 9);
+
+    let value: Box<OsStr> = OsStr::new("Hello").into();
+    assert_eq!(value.get_heap_size(), 5);
+
+    let value: Box<CStr> = c"Hello".into();
+    assert_eq!(value.get_heap_size(), 6);
+
+    let value: Arc<Path> = Arc::from(Path::new("/tmp"));
+    assert_eq!(value.get_heap_size(), (counters + 4).next_multiple_of(8));
+}
+
+#[test]
+fn shared_control_block() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[repr(align(32))]
+    struct Aligned(u8);
+
+    impl GetSize for Aligned {}
+
+    let counters = 2 * size_of::<usize>();
+
+    // The value is stored right behind the counters.
+    assert_eq!(Arc::new(0u64).get_heap_size(), counters + 8);
+    assert_eq!(Rc::new(0u64).get_heap_size(), counters + 8);
+
+    // The allocation gets padded to the alignment of the counters.
+    assert_eq!(
+        Arc::new(0u8).get_heap_size(),
+        (counters + 1).next_multiple_of(align_of::<usize>())
+    );
+
+    // The value is placed at its own alignment.
+    assert_eq!(Arc::new(Aligned(0)).0, 0);
+    assert_eq!(Arc::new(Aligned(0)).get_heap_size(), 64);
+}
+
+#[test]
+fn orphaned_weak() {
+    use std::rc::{Rc, Weak as RcWeak};
+    use std::sync::{Arc, Weak};
+
+    let allocation = 2 * size_of::<usize>() + size_of::<String>();
+
+    let strong = Arc::new(String::from("Hello"));
+    let weak = Arc::downgrade(&strong);
+
+    let mut tracker = StandardTracker::default();
+    tracker.set_count_orphaned_weak(true);
+
+    // Strong references are still alive and account for the allocation.
+    let (size, mut tracker) = weak.get_heap_size_with_tracker(tracker);
+    assert_eq!(size, 0);
+
+    drop(strong);
+    assert_eq!(weak.get_heap_size(), 0);
+
+    // Only the memory of the dropped `String` remains allocated.
+    let weaks = vec![Weak::clone(&weak), weak];
+    let (size, _) = weaks.get_heap_size_with_tracker(&mut tracker);
+    assert_eq!(
+        size,
+        weaks.capacity() * size_of::<Weak<String>>() + allocation
+    );
+
+    let weak = Rc::downgrade(&Rc::new(String::new()));
+    let (size, _) = weak.get_heap_size_with_tracker(&mut tracker);
+    assert_eq!(size, allocation);
+
+    // Weak references which never pointed to an allocation own nothing.
+    let (size, _) = RcWeak::<String>::new().get_heap_size_with_tracker(&mut tracker);
+    assert_eq!(size, 0);
+}
+
+#[test]
+fn weak_with_lifetimes() {
+    use std::rc::{Rc, Weak};
+
+    #[derive(GetSize)]
+    struct Borrowing<'a> {
+        parent: Weak<&'a str>,
+    }
+
+    let name = String::from("Hello");
+    let value = Borrowing {
+        parent: Rc::downgrade(&Rc::new(name.as_str())),
+    };
+
+    let mut tracker = StandardTracker::default();
+    tracker.set_count_orphaned_weak(true);
+
+    // The tracker keeps the allocation alive, even beyond the lifetime of the borrowed `String`.
+    let (size, tracker) = value.get_heap_size_with_tracker(tracker);
+    assert_eq!(size, 2 * size_of::<usize>() + size_of::<&str>());
+    drop(value);
+    drop(name);
+    drop(tracker);
+}
+
+#[derive(GetSize)]
+struct ReportEntry {
+    id: u64,
+    payload: Vec<u8>,
+    #[get_size(size = 100)]
+    buffer: u32,
+    #[get_size(ignore)]
+    ignored: String,
+}
+
+#[derive(GetSize)]
+struct ReportIndex {
+    entries: Vec<ReportEntry>,
+    shared: std::sync::Arc<String>,
+}
+
+#[derive(GetSize)]
+struct ReportCache {
+    index: ReportIndex,
+    name: Option<Box<str>>,
+}
+
+#[test]
+fn size_report() {
+    use std::sync::Arc;
+
+    let cache = ReportCache {
+        index: ReportIndex {
+            entries: vec![
+                ReportEntry {
+                    id: 1,
+                    payload: vec![0; 10],
+                    buffer: 0,
+                    ignored: "Hello".into(),
+                },
+                ReportEntry {
+                    id: 2,
+                    payload: vec![0; 20],
+                    buffer: 0,
+                    ignored: String::new(),
+                },
+            ],
+            shared: Arc::new("Hello".into()),
+        },
+        name: Some("cache".into()),
+    };
+
+    let report = cache.get_size_report();
+    assert_eq!(report.name(), "root");
+    assert_eq!(report.total(), cache.get_size());
+    assert_eq!(report.stack(), size_of::<ReportCache>());
+
+    let payload = report.find("root.index.entries[*].payload").unwrap();
+    assert_eq!(payload.stack(), 2 * size_of::<Vec<u8>>());
+    assert_eq!(payload.heap(), 30);
+
+    let buffer = report.find("root.index.entries[*].buffer").unwrap();
+    assert_eq!(buffer.heap(), 200);
+    assert!(report.find("root.index.entries[*].ignored").is_none());
+
+    let shared = report.find("root.index.shared").unwrap();
+    assert_eq!(shared.heap(), 0);
+    assert_eq!(
+        shared.shared_heap(),
+        2 * size_of::<usize>() + size_of::<String>() + 5
+    );
+    assert_eq!(report.shared_heap(), shared.shared_heap());
+
+    let name = report.find("root.name").unwrap();
+    assert_eq!(name.heap(), 5);
+
+    let mut paths = Vec::new();
+    report.for_each(|path, _node| paths.push(path.to_owned()));
+    assert_eq!(
+        paths,
+        [
+            "root",
+            "root.index",
+            "root.index.entries",
+            "root.index.entries[*]",
+            "root.index.entries[*].id",
+            "root.index.entries[*].payload",
+            "root.index.entries[*].payload[*]",
+            "root.index.entries[*].buffer",
+            "root.index.shared",
+            "root.name",
+        ]
+    );
+}
+
+#[test]
+fn size_report_enum() {
+    let values = vec![
+        TestEnum::Variant2("Hello".into()),
+        TestEnum::Variant7 {
+            x: "a".into(),
+            y: "bc".into(),
+        },
+        TestEnum::Variant2("world!".into()),
+        TestEnum::Variant6,
+    ];
+
+    let report = values.get_size_report();
+    assert_eq!(report.total(), values.get_size());
+
+    let variant = report.find("root[*].Variant2").unwrap();
+    assert_eq!(variant.stack(), 2 * size_of::<TestEnum>());
+    assert_eq!(variant.heap(), 11);
+    assert_eq!(report.find("root[*].Variant2.0").unwrap().heap(), 11);
+    assert_eq!(report.find("root[*].Variant7.y").unwrap().heap(), 2);
+    assert_eq!(report.find("root[*].Variant6").unwrap().heap(), 0);
+}
+
+mod in_house {
+    use std::sync::Arc;
+
+    pub struct Span<T> {
+        pub start: T,
+        pub end: T,
+    }
+
+    pub struct SmallMap<K, V> {
+        pub entries: Vec<(K, V)>,
+    }
+
+    impl<K, V> SmallMap<K, V> {
+        pub fn capacity(&self) -> usize {
+            self.entries.capacity()
+        }
+    }
+
+    impl<'a, K, V> IntoIterator for &'a SmallMap<K, V> {
+        type Item = (&'a K, &'a V);
+        type IntoIter =
+            std::iter::Map<std::slice::Iter<'a, (K, V)>, fn(&'a (K, V)) -> (&'a K, &'a V)>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.entries.iter().map(|(k, v)| (k, v))
+        }
+    }
+
+    pub struct Interner {
+        pub strings: Box<[Arc<String>]>,
+    }
+
+    impl<'a> IntoIterator for &'a Interner {
+        type Item = &'a Arc<String>;
+        type IntoIter = std::slice::Iter<'a, Arc<String>>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.strings.iter()
+        }
+    }
+}
+
+get_size2::impl_get_size!(impl<T> fields for in_house::Span<T> { start, end } where T: GetSize);
+get_size2::impl_get_size!(impl<K, V> map for in_house::SmallMap<K, V> where K: GetSize, V: GetSize);
+get_size2::impl_get_size!(impl collection_without_capacity for in_house::Interner);
+
+#[test]
+fn impl_get_size_macro() {
+    use std::sync::Arc;
+
+    use in_house::{Interner, SmallMap, Span};
+
+    let span = Span {
+        start: String::from("a"),
+        end: String::from("bc"),
+    };
+    assert_eq!(span.get_heap_size(), 3);
+
+    let report = span.get_size_report();
+    assert_eq!(report.total(), span.get_size());
+    assert_eq!(report.find("root.end").unwrap().heap(), 2);
+
+    let shared = Arc::new(String::from("Hello"));
+    let shared_size = 2 * size_of::<usize>() + size_of::<String>() + 5;
+
+    let mut map = SmallMap {
+        entries: Vec::with_capacity(4),
+    };
+    map.entries.push((1u64, Arc::clone(&shared)));
+    map.entries.push((2u64, Arc::clone(&shared)));
+    assert_eq!(
+        map.get_heap_size(),
+        4 * (size_of::<u64>() + size_of::<Arc<String>>()) + shared_size
+    );
+
+    let report = map.get_size_report();
+    assert_eq!(report.total(), map.get_size());
+    assert_eq!(
+        report.find("root[value]").unwrap().shared_heap(),
+        shared_size
+    );
+
+    let interner = Interner {
+        strings: vec![Arc::clone(&shared); 3].into_boxed_slice(),
+    };
+    assert_eq!(
+        interner.get_heap_size(),
+        3 * size_of::<Arc<String>>() + shared_size
+    );
+
+    let (total, tracker) = interner.get_heap_size_with_tracker(StandardTracker::default());
+    let (total_add, _) = map.get_heap_size_with_tracker(tracker);
+    assert_eq!(
+        total + total_add,
+        3 * size_of::<Arc<String>>()
+            + 4 * (size_of::<u64>() + size_of::<Arc<String>>())
+            + shared_size
+    );
+}
+
+#[derive(GetSize)]
+struct TestAllocations {
+    name: String,
+    ids: Vec<u64>,
+    boxed: Box<[u8; 20]>,
+    shared: Vec<std::sync::Arc<String>>,
+}
+
+#[test]
+#[cfg(target_pointer_width = "64")]
+fn allocator_models() {
+    use std::alloc::Layout;
+    use std::sync::Arc;
+
+    let sizes = |model: &dyn AllocatorModel| {
+        [1, 8, 17, 24, 25, 72, 129, 14_337, 200_000]
+            .map(|size| model.allocation_size(Layout::from_size_align(size, 1).unwrap()))
+    };
+    assert_eq!(
+        sizes(&JemallocModel),
+        [8, 8, 32, 32, 32, 80, 160, 16_384, 229_376]
+    );
+    assert_eq!(
+        sizes(&MimallocModel),
+        [8, 8, 24, 24, 32, 80, 160, 16_384, 200_704]
+    );
+    assert_eq!(
+        sizes(&GlibcMallocModel),
+        [32, 32, 32, 32, 48, 80, 144, 14_352, 200_704]
+    );
+
+    let string = String::with_capacity(17);
+    assert_eq!(string.get_heap_size(), 17);
+    assert_eq!(string.get_heap_size_with_model(JemallocModel), 32);
+
+    let shared = Arc::new(String::with_capacity(17));
+    let value = TestAllocations {
+        name: String::with_capacity(17),
+        ids: Vec::with_capacity(3),
+        boxed: Box::new([0; 20]),
+        shared: vec![Arc::clone(&shared), Arc::clone(&shared)],
+    };
+    assert_eq!(
+        value.get_heap_size(),
+        17 + 24 + 20 + 2 * size_of::<Arc<String>>() + 16 + size_of::<String>() + 17
+    );
+    assert_eq!(
+        value.get_heap_size_with_model(JemallocModel),
+        32 + 32 + 32 + 16 + 48 + 32
+    );
+
+    let tracker = AllocatorModelTracker::new(JemallocModel, StandardTracker::default());
+    let (report, _) = value.get_size_report_with_tracker(tracker);
+    assert_eq!(
+        report.total(),
+        size_of::<TestAllocations>() + value.get_heap_size_with_model(JemallocModel)
+    );
+}
+
+#[test]
+fn std_impls_match_allocations() {
+    use std::borrow::Cow;
+    use std::collections::{
+        BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque,
+    };
+    use std::ffi::{CString, OsString};
+    use std::io::{BufReader, BufWriter};
+    use std::path::PathBuf;
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+    assert_heap_size_matches(|| 42u64);
+    assert_heap_size_matches(|| (1u8, 'a', 2.0f64));
+    assert_heap_size_matches(|| 1..10);
+    assert_heap_size_matches(|| String::from("a")..String::from("bc"));
+    assert_heap_size_matches(|| String::from("a")..=String::from("bc"));
+    assert_heap_size_matches(|| Cow::<String>::Owned(String::from("Hello")));
+    let borrowed = String::from("Hello");
+    assert_heap_size_matches(|| Cow::Borrowed(&borrowed));
+    assert_heap_size_matches(|| Some(String::from("Hello")));
+    assert_heap_size_matches(|| Ok::<_, String>(vec![1u32, 2, 3])).unwrap();
+    assert_heap_size_matches(|| [String::from("a"), String::from("bc")]);
+
+    assert_heap_size_matches(|| String::with_capacity(17));
+    assert_heap_size_matches(|| CString::new("Hello").unwrap());
+    assert_heap_size_matches(|| OsString::from("Hello"));
+    assert_heap_size_matches(|| PathBuf::from("/tmp/hello"));
+    assert_heap_size_matches(|| BufReader::new(&b"Hello"[..]));
+    assert_heap_size_matches(|| BufWriter::with_capacity(100, Vec::<u8>::with_capacity(10)));
+
+    assert_heap_size_matches(|| vec![String::from("a"), String::from("bc")]);
+    assert_heap_size_matches(|| (0..10u64).collect::<VecDeque<_>>());
+    assert_heap_size_matches(|| (0..10u64).collect::<BinaryHeap<_>>());
+    assert_heap_size_matches(|| (0..10u64).collect::<LinkedList<_>>());
+    assert_heap_size_matches(|| (0..1000u64).map(|i| (i, i)).collect::<BTreeMap<_, _>>());
+    assert_heap_size_matches(|| (0..1000u32).collect::<BTreeSet<_>>());
+    assert_heap_size_matches(|| {
+        (0..100u64)
+            .map(|i| (i, i.to_string()))
+            .collect::<HashMap<_, _>>()
+    });
+    assert_heap_size_matches(|| (0..100u64).collect::<HashSet<_>>());
+
+    assert_heap_size_matches(|| Box::new(String::from("Hello")));
+    assert_heap_size_matches(|| Box::<str>::from("Hello"));
+    assert_heap_size_matches(|| Box::<[u16]>::from([1, 2, 3]));
+    assert_heap_size_matches(|| Rc::new(String::from("Hello")));
+    assert_heap_size_matches(|| Arc::<[u8]>::from([1, 2, 3]));
+    assert_heap_size_matches(|| {
+        let rc = Rc::new(String::from("Hello"));
+        (Rc::downgrade(&rc), rc)
+    });
+    assert_heap_size_matches(|| {
+        let arc = Arc::new(vec![1u8, 2, 3]);
+        (Arc::clone(&arc), arc)
+    });
+
+    assert_heap_size_matches(|| Mutex::new(String::from("Hello")));
+    assert_heap_size_matches(|| RwLock::new(String::from("Hello")));
+    assert_heap_size_matches(|| OnceLock::from(String::from("Hello")));
+}
+
+#[test]
+fn hash_tables_after_removals_match_allocations() {
+    use std::collections::{HashMap, HashSet};
+
+    // Entries removed from a sparse table do not leave tombstones behind.
+    assert_heap_size_matches(|| {
+        let mut value = HashMap::with_capacity(1000);
+        value.extend((0..100u64).map(|i| (i, i.to_string())));
+        value.retain(|i, _| i % 2 == 0);
+        value
+    });
+    assert_heap_size_matches(|| {
+        let mut value: HashSet<u64> = HashSet::with_capacity(1000);
+        value.extend(0..100);
+        for i in 0..50 {
+            value.remove(&i);
+        }
+        value
+    });
+    assert_heap_size_matches(|| {
+        let mut value: HashMap<u64, u64> = (0..100).map(|i| (i, i)).collect();
+        value.clear();
+        value
+    });
+}
+
+#[test]
+fn feature_impls_match_allocations() {
+    use std::hash::RandomState;
+
+    assert_heap_size_matches(|| bytes::Bytes::copy_from_slice(b"Hello"));
+    assert_heap_size_matches(|| bytes::Bytes::from_static(b"Hello"));
+    assert_heap_size_matches(|| bytes::Bytes::from_static(b"Hello World!").slice(6..));
+    assert_heap_size_matches(|| bytes::Bytes::from(vec![1u8; 64]));
+    assert_heap_size_matches(|| bytes::Bytes::from(String::from("Hello")));
+    assert_heap_size_matches(|| bytes::Bytes::from(vec![1u8; 64].into_boxed_slice()));
+    assert_heap_size_matches(|| {
+        let mut value = bytes::BytesMut::with_capacity(64);
+        value.extend_from_slice(b"Hello");
+        value
+    });
+    assert_heap_size_matches(|| {
+        url::Url::parse("https://example.com/path?query#fragment").unwrap()
+    });
+    assert_heap_size_matches(|| chrono::DateTime::from_timestamp(0, 0).unwrap());
+    assert_heap_size_matches(|| {
+        let mut value = hashbrown::HashMap::with_hasher(RandomState::new());
+        value.extend((0..100u64).map(|i| (i, i.to_string())));
+        value
+    });
+    assert_heap_size_matches(|| {
+        let mut value = hashbrown::HashSet::with_hasher(RandomState::new());
+        value.extend(0..100u64);
+        value
+    });
+    assert_heap_size_matches(|| {
+        let mut value = hashbrown::HashTable::new();
+        for i in 0..100u64 {
+            value.insert_unique(i, i, |i| *i);
+        }
+        value
+    });
+    assert_heap_size_matches(|| smallvec::SmallVec::<[u64; 2]>::from_vec(vec![1, 2, 3]));
+    assert_heap_size_matches(|| {
+        let mut value = smallvec::SmallVec::<[u64; 2]>::with_capacity(100);
+        value.extend([1, 2, 3]);
+        value
+    });
+    assert_heap_size_matches(|| {
+        let mut value = smallvec::SmallVec::<[String; 2]>::new();
+        value.extend((0..10).map(|i| i.to_string()));
+        value.truncate(1);
+        value
+    });
+    assert_heap_size_matches(|| smallvec::SmallVec::<[u64; 4]>::with_capacity(2));
+    assert_heap_size_matches(|| smallvec::SmallVec::<[String; 2]>::from_iter([String::from("a")]));
+    assert_heap_size_matches(|| compact_str::CompactString::from("a".repeat(100)));
+    assert_heap_size_matches(|| compact_str::CompactString::from("short"));
+}
+
+#[derive(GetSize)]
+struct TestLocked {
+    name: std::sync::Mutex<String>,
+    values: std::sync::RwLock<Vec<u64>>,
+}
+
+#[test]
+fn skip_locked_values() {
+    let value = TestLocked {
+        name: std::sync::Mutex::new(String::from("Hello")),
+        values: std::sync::RwLock::new(vec![1, 2, 3]),
+    };
+
+    let mut tracker = StandardTracker::default();
+    tracker.set_lock_policy(LockPolicy::Skip);
+
+    let (size, mut tracker) = value.get_heap_size_with_tracker(tracker);
+    assert_eq!(size, 5 + 3 * size_of::<u64>());
+
+    {
+        // Blocking would deadlock while this thread holds the locks itself.
+        let _name = value.name.lock().unwrap();
+        let _values = value.values.write().unwrap();
+
+        let (size, tr) = value.get_heap_size_with_tracker(tracker);
+        assert_eq!(size, 0);
+        assert_eq!(tr.skipped_locks(), 2);
+
+        let (report, tr) = value.get_size_report_with_tracker(tr);
+        let report = report.named("root");
+        assert_eq!(report.heap(), 0);
+        assert_eq!(report.skipped(), 2);
+        assert_eq!(report.find("root.values").unwrap().skipped(), 1);
+        tracker = tr;
+    }
+
+    // Readers do not block each other.
+    let _values = value.values.read().unwrap();
+    let (size, tracker) = value.get_heap_size_with_tracker(tracker);
+    assert_eq!(size, 5 + 3 * size_of::<u64>());
+    assert_eq!(tracker.skipped_locks(), 4);
+}
+
+#[test]
+fn poisoned_locks() {
+    use std::panic::catch_unwind;
+    use std::sync::{Mutex, RwLock};
+
+    let value = TestLocked {
+        name: Mutex::new(String::from("Hello")),
+        values: RwLock::new(vec![1, 2, 3]),
+    };
+    let mut tracker = Mutex::new(StandardTracker::default());
+
+    let _ = catch_unwind(|| {
+        let _name = value.name.lock().unwrap();
+        let _values = value.values.write().unwrap();
+        let _tracker = tracker.lock().unwrap();
+        panic!("Poisons all locks");
+    });
+    assert!(value.name.is_poisoned(), "The Mutex must be poisoned");
+    assert!(tracker.is_poisoned(), "The tracker must be poisoned");
+
+    assert_eq!(value.get_heap_size(), 5 + 3 * size_of::<u64>());
+
+    let (report, _) = value.get_size_report_with_tracker(&mut tracker);
+    assert_eq!(report.heap(), 5 + 3 * size_of::<u64>());
+    assert_eq!(report.skipped(), 0);
+}
+
+#[test]
+fn try_get_heap_size() {
+    #[derive(GetSize)]
+    enum TestEnum {
+        Locked(TestLocked),
+        Values(Vec<String>, Option<Box<str>>),
+    }
+
+    let value = TestEnum::Values(
+        vec![String::from("a"), String::from("bc")],
+        Some("def".into()),
+    );
+    assert_eq!(value.try_get_heap_size(), Ok(value.get_heap_size()));
+    assert_eq!(value.try_get_size(), Ok(value.get_size()));
+
+    let value = TestEnum::Locked(TestLocked {
+        name: std::sync::Mutex::new(String::from("Hello")),
+        values: std::sync::RwLock::new(vec![1, 2, 3]),
+    });
+    assert_eq!(value.try_get_heap_size(), Ok(5 + 3 * size_of::<u64>()));
+
+    let TestEnum::Locked(locked) = &value else {
+        unreachable!()
+    };
+    let _name = locked.name.lock().unwrap();
+
+    let mut tracker = StandardTracker::default();
+    tracker.set_lock_policy(LockPolicy::Fail);
+
+    let error = value.try_get_heap_size_with_tracker(tracker).unwrap_err();
+    assert_eq!(error, GetSizeError::locked::<std::sync::Mutex<String>>());
+    assert!(
+        error.to_string().contains("Mutex"),
+        "The error must name the locked type"
+    );
+
+    // The infallible methods skip the locked value instead.
+    let mut tracker = StandardTracker::default();
+    tracker.set_lock_policy(LockPolicy::Fail);
+    let (size, tracker) = value.get_heap_size_with_tracker(tracker);
+    assert_eq!(size, 3 * size_of::<u64>());
+    assert_eq!(tracker.skipped_locks(), 1);
+}
+
+#[test]
+fn interior_mutability() {
+    use std::cell::{Cell, LazyCell, OnceCell, RefCell};
+    use std::rc::Rc;
+    use std::sync::LazyLock;
+
+    #[derive(GetSize)]
+    struct Node {
+        name: String,
+        children: Vec<Rc<RefCell<Node>>>,
+    }
+
+    let cell = Cell::new(5u64);
+    assert_eq!(cell.get_heap_size(), 0);
+
+    let once = OnceCell::new();
+    assert_eq!(once.get_heap_size(), 0);
+    once.set(String::from("Hello")).unwrap();
+    assert_eq!(once.get_heap_size(), 5);
+
+    // Lazy values are not forced by measuring them.
+    let lazy = LazyCell::new(|| String::from("Hello"));
+    assert_eq!(lazy.get_heap_size(), 0);
+    assert_eq!(LazyCell::force(&lazy).len(), 5);
+    assert_eq!(lazy.get_heap_size(), 5);
+
+    let lazy = LazyLock::new(|| vec![1u8, 2, 3]);
+    assert_eq!(lazy.get_heap_size(), 0);
+    assert_eq!(LazyLock::get(&lazy), None);
+
+    // Shared nodes are only counted once.
+    let leaf = Rc::new(RefCell::new(Node {
+        name: String::from("leaf"),
+        children: Vec::new(),
+    }));
+    let root = RefCell::new(Node {
+        name: String::from("root"),
+        children: vec![Rc::clone(&leaf), Rc::clone(&leaf)],
+    });
+
+    let leaf_size = size_of::<usize>() * 2 + size_of::<RefCell<Node>>() + 4;
+    let root_size = 4 + 2 * size_of::<Rc<RefCell<Node>>>() + leaf_size;
+    assert_eq!(root.get_heap_size(), root_size);
+    assert_eq!(root.try_get_heap_size(), Ok(root_size));
+
+    let _borrowed = leaf.borrow_mut();
+    assert_eq!(
+        root.try_get_heap_size(),
+        Err(GetSizeError::borrowed::<RefCell<Node>>())
+    );
+
+    let (report, tracker) = root.get_size_report_with_tracker(StandardTracker::default());
+    assert_eq!(report.heap() + report.shared_heap(), root_size - 4);
+    assert_eq!(report.skipped(), 1);
+    assert_eq!(tracker.skipped_locks(), 1);
+}
+
+#[test]
+fn cyclic_rc_graphs() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(GetSize)]
+    struct ListNode {
+        value: u64,
+        prev: Option<Rc<RefCell<ListNode>>>,
+        next: Option<Rc<RefCell<ListNode>>>,
+    }
+
+    let nodes: Vec<_> = (0..3)
+        .map(|value| {
+            Rc::new(RefCell::new(ListNode {
+                value,
+                prev: None,
+                next: None,
+            }))
+        })
+        .collect();
+
+    for pair in nodes.windows(2) {
+        pair[0].borrow_mut().next = Some(Rc::clone(&pair[1]));
+        pair[1].borrow_mut().prev = Some(Rc::clone(&pair[0]));
+    }
+
+    let node_size = 2 * size_of::<usize>() + size_of::<RefCell<ListNode>>();
+
+    // Every node is reached through a back-edge, but only counted once.
+    assert_eq!(nodes[0].get_heap_size(), 3 * node_size);
+    assert_eq!(nodes[1].get_heap_size(), 3 * node_size);
+    assert_eq!(
+        nodes.get_heap_size(),
+        3 * size_of::<usize>() + 3 * node_size
+    );
+
+    let mut tracker = StandardTracker::default();
+    tracker.set_detect_cycles(true);
+    let (size, tracker) = nodes[0].get_heap_size_with_tracker(tracker);
+    assert_eq!(size, 3 * node_size);
+    assert_eq!(tracker.cycles(), 2);
+
+    let mut tracker = StandardTracker::default();
+    tracker.set_detect_cycles(true);
+    let (report, tracker) = nodes[2].get_size_report_with_tracker(tracker);
+    assert_eq!(report.shared_heap(), 3 * node_size);
+    assert_eq!(tracker.cycles(), 2);
+
+    for node in &nodes {
+        node.borrow_mut().prev = None;
+    }
+}
+
+#[test]
+fn cyclic_arc_graphs() {
+    use std::sync::{Arc, Mutex};
+
+    #[derive(GetSize)]
+    struct GraphNode {
+        name: String,
+        edges: Vec<Arc<Mutex<GraphNode>>>,
+    }
+
+    let node = |name: &str| {
+        Arc::new(Mutex::new(GraphNode {
+            name: String::from(name),
+            edges: Vec::new(),
+        }))
+    };
+    let node_size = 2 * size_of::<usize>() + size_of::<Mutex<GraphNode>>();
+    let edge_size = size_of::<Arc<Mutex<GraphNode>>>();
+
+    // A node pointing at itself and at another node, which points back.
+    let a = node("a");
+    let b = node("b");
+    a.lock().unwrap().edges = vec![Arc::clone(&a), Arc::clone(&b)];
+    b.lock().unwrap().edges = vec![Arc::clone(&a)];
+
+    let mut tracker = StandardTracker::default();
+    tracker.set_detect_cycles(true);
+    let (size, tracker) = a.get_heap_size_with_tracker(tracker);
+    assert_eq!(size, 2 * node_size + 2 + 3 * edge_size);
+    assert_eq!(tracker.cycles(), 2);
+    assert_eq!(a.try_get_heap_size(), Ok(size));
+
+    // Shared nodes without a cycle are not reported.
+    let leaf = node("leaf");
+    let root = node("root");
+    let left = node("left");
+    left.lock().unwrap().edges = vec![Arc::clone(&leaf)];
+    root.lock().unwrap().edges = vec![Arc::clone(&left), Arc::clone(&leaf)];
+
+    let mut tracker = StandardTracker::default();
+    tracker.set_detect_cycles(true);
+    let (size, tracker) = root.get_heap_size_with_tracker(tracker);
+    assert_eq!(size, 3 * node_size + 12 + 3 * edge_size);
+    assert_eq!(tracker.cycles(), 0);
+
+    a.lock().unwrap().edges.clear();
+    b.lock().unwrap().edges.clear();
+}
+
+#[test]
+fn scoped_tracker() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    let value = Arc::new(String::from("hello"));
+    let values = vec![Arc::clone(&value); 10];
+    let shared = Rc::new(vec![1u32, 2, 3]);
+    let nested = (Rc::clone(&shared), Rc::clone(&shared));
+
+    let (size, tracker) = ScopedTracker::measure(&values);
+    assert_eq!(size, values.get_heap_size());
+
+    // Only the address has been recorded, the tracker does not own a reference.
+    assert_eq!(Arc::strong_count(&value), 11);
+    assert_eq!(Arc::weak_count(&value), 0);
+    drop(tracker);
+
+    let mut tracker = ScopedTracker::new(&nested);
+    tracker.tracker_mut().set_lock_policy(LockPolicy::Skip);
+    let (report, tracker) = nested.get_size_report_with_tracker(tracker);
+    assert_eq!(report.total(), nested.get_size());
+    assert_eq!(Rc::strong_count(&shared), 3);
+    assert_eq!(tracker.tracker().skipped_locks(), 0);
+}
+
+#[test]
+fn tracker_options() {
+    use std::sync::{Mutex, RwLock};
+
+    let mut tracker = StandardTracker::default();
+    tracker.set_lock_policy(LockPolicy::Skip);
+    tracker.set_btree_occupancy(BTreeOccupancy::Minimal);
+    let options = tracker.options();
+    assert_eq!(options.tracking_mode, TrackingMode::Strong);
+    assert_eq!(options.lock_policy, LockPolicy::Skip);
+    assert_eq!(options.btree_occupancy, BTreeOccupancy::Minimal);
+
+    // The options are passed through every wrapper.
+    let configured = || {
+        let mut tracker = StandardTracker::default();
+        tracker.set_lock_policy(LockPolicy::Skip);
+        tracker.set_btree_occupancy(BTreeOccupancy::Minimal);
+        tracker
+    };
+    let mut tracker = configured();
+    assert_eq!(GetSizeTracker::options(&&mut tracker), options);
+    assert_eq!(Box::new(configured()).options(), options);
+    assert_eq!(Mutex::new(configured()).options(), options);
+    assert_eq!(RwLock::new(configured()).options(), options);
+    let tracker: &mut dyn DynGetSizeTracker = &mut tracker;
+    assert_eq!(tracker.options(), options);
+    let tracker = AllocatorModelTracker::new(JemallocModel, configured());
+    assert_eq!(tracker.options(), options);
+
+    // Trackers which do not keep the values alive only change the tracking mode.
+    let mut tracker = WeakTracker::new();
+    tracker.tracker_mut().set_lock_policy(LockPolicy::Fail);
+    assert_eq!(tracker.options().tracking_mode, TrackingMode::Weak);
+    assert_eq!(tracker.options().lock_policy, LockPolicy::Fail);
+
+    let value = 0u8;
+    let tracker = ScopedTracker::new(&value);
+    assert_eq!(tracker.options().tracking_mode, TrackingMode::Address);
+}
+
+#[test]
+fn weak_tracker() {
+    use std::sync::Arc;
+
+    let value = Arc::new(String::from("hello"));
+    let weak = Arc::downgrade(&value);
+    let values = vec![Arc::clone(&value); 10];
+
+    let (size, mut tracker) = values.get_heap_size_with_tracker(WeakTracker::new());
+    assert_eq!(size, values.get_heap_size());
+    assert_eq!(Arc::strong_count(&value), 11);
+    assert_eq!(Arc::weak_count(&value), 2);
+
+    // Objects seen before are not counted again.
+    let (size, tr) = value.get_heap_size_with_tracker(tracker);
+    assert_eq!(size, 0);
+    tracker = tr;
+
+    // The tracker does not keep the value alive.
+    drop(values);
+    drop(value);
+    assert!(weak.upgrade().is_none(), "The value must have been dropped");
+
+    tracker.clear();
+}
+
+#[test]
+fn try_get_heap_size_nested() {
+    use std::cell::RefCell;
+    use std::collections::{BTreeMap, HashMap, LinkedList};
+    use std::sync::Mutex;
+
+    #[derive(GetSize)]
+    struct Huge {
+        #[get_size(size_fn = max_size)]
+        value: String,
+    }
+
+    fn max_size(_value: &String) -> usize {
+        usize::MAX
+    }
+
+    let failing = || {
+        let mut tracker = StandardTracker::default();
+        tracker.set_lock_policy(LockPolicy::Fail);
+        tracker
+    };
+    let locked = GetSizeError::locked::<Mutex<String>>();
+    let borrowed = GetSizeError::borrowed::<RefCell<String>>();
+
+    let map = HashMap::from([(1u64, Mutex::new(String::from("Hello")))]);
+    let tree = BTreeMap::from([(1u64, Mutex::new(String::from("Hello")))]);
+    assert_eq!(map.try_get_heap_size(), Ok(map.get_heap_size()));
+
+    let _guard = map[&1].lock().unwrap();
+    let _tree_guard = tree[&1].lock().unwrap();
+    assert_eq!(
+        map.try_get_heap_size_with_tracker(failing()).err(),
+        Some(locked)
+    );
+    assert_eq!(
+        tree.try_get_heap_size_with_tracker(failing()).err(),
+        Some(locked)
+    );
+
+    let cell = RefCell::new(String::from("Hello"));
+    let array = [RefCell::new(String::from("Hello"))];
+    let tuple = (0u8, RefCell::new(String::from("Hello")));
+    let result: Result<RefCell<String>, ()> = Ok(RefCell::new(String::from("Hello")));
+    let list = LinkedList::from([RefCell::new(String::from("Hello"))]);
+    assert_eq!(array.try_get_heap_size(), Ok(5));
+    assert_eq!(tuple.try_get_heap_size(), Ok(5));
+    assert_eq!(result.try_get_heap_size(), Ok(5));
+
+    let _borrows = (
+        cell.borrow_mut(),
+        array[0].borrow_mut(),
+        tuple.1.borrow_mut(),
+        result.as_ref().unwrap().borrow_mut(),
+        list.front().unwrap().borrow_mut(),
+    );
+    assert_eq!(cell.try_get_heap_size(), Err(borrowed));
+    assert_eq!(array.try_get_heap_size(), Err(borrowed));
+    assert_eq!(tuple.try_get_heap_size(), Err(borrowed));
+    assert_eq!(result.try_get_heap_size(), Err(borrowed));
+    assert_eq!(list.try_get_heap_size(), Err(borrowed));
+    // Sizes which do not fit into a usize fail instead of wrapping around.
+    let values = (
+        Huge {
+            value: String::new(),
+        },
+        String::from("Hello"),
+    );
+    assert_eq!(
+        values.try_get_heap_size(),
+        Err(GetSizeError::overflow::<(Huge, String)>())
+    );
+}
+
+#[test]
+fn orphaned_weak_cycles() {
+    use std::sync::{Arc, Mutex};
+
+    #[derive(GetSize)]
+    struct Node {
+        edges: Vec<Arc<Mutex<Node>>>,
+    }
+
+    let weak = Arc::downgrade(&Arc::new(String::from("Hello")));
+    let weaks = vec![std::sync::Weak::clone(&weak), weak];
+
+    let mut tracker = StandardTracker::default();
+    tracker.set_count_orphaned_weak(true);
+    tracker.set_detect_cycles(true);
+    let (size, mut tracker) = weaks.get_heap_size_with_tracker(tracker);
+    assert!(size > 0, "The orphaned allocation must be counted");
+    assert_eq!(tracker.cycles(), 0);
+
+    // Clearing the tracker resets its counters.
+    let node = Arc::new(Mutex::new(Node { edges: Vec::new() }));
+    node.lock().unwrap().edges.push(Arc::clone(&node));
+    let (_, tr) = node.get_heap_size_with_tracker(tracker);
+    tracker = tr;
+    assert_eq!(tracker.cycles(), 1);
+
+    tracker.clear();
+    assert_eq!(tracker.cycles(), 0);
+    assert_eq!(tracker.skipped_locks(), 0);
+
+    node.lock().unwrap().edges.clear();
+}
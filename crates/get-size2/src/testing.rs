@@ -0,0 +1,170 @@
+//! Helpers for validating implementations of [`GetSize`] against the allocations which have
+//! actually been made.
+//!
+//! Install the [`CountingAllocator`] as the global allocator of a test binary, then use
+//! [`assert_heap_size_matches`] to compare the heap size reported for a value with the bytes
+//! allocated while building it.
+//!
+//! ```
+//! use get_size2::testing::{CountingAllocator, assert_heap_size_matches};
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: CountingAllocator = CountingAllocator::system();
+//!
+//! assert_heap_size_matches(|| vec![String::from("Hello"), String::from("World!")]);
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::GetSize;
+
+thread_local! {
+    /// The number of bytes allocated by the current thread, minus the bytes it deallocated.
+    static ALLOCATED: Cell<isize> = const { Cell::new(0) };
+}
+
+/// Whether the [`CountingAllocator`] has served any allocation.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Adds the `allocated` bytes to the balance of the current thread, subtracting the
+/// `deallocated` ones.
+fn count(allocated: usize, deallocated: usize) {
+    INSTALLED.store(true, Ordering::Relaxed);
+
+    // The balance is no longer available while the thread is shutting down.
+    let _ = ALLOCATED.try_with(|balance| {
+        balance.set(
+            balance
+                .get()
+                .wrapping_add_unsigned(allocated)
+                .wrapping_sub_unsigned(deallocated),
+        );
+    });
+}
+
+/// A global allocator which wraps another allocator, counting the bytes allocated by each
+/// thread.
+///
+/// The requested size of each allocation is counted, just like [`GetSize::get_heap_size`]
+/// reports it. Since the counters are local to each thread, tests running in parallel do not
+/// influence each other.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CountingAllocator<A = System> {
+    allocator: A,
+}
+
+impl CountingAllocator {
+    /// Creates a new counting allocator, wrapping the [`System`] allocator.
+    #[must_use]
+    pub const fn system() -> Self {
+        Self { allocator: System }
+    }
+}
+
+impl<A> CountingAllocator<A> {
+    /// Creates a new counting allocator, wrapping the given `allocator`.
+    #[must_use]
+    pub const fn new(allocator: A) -> Self {
+        Self { allocator }
+    }
+}
+
+// Forwarding to the wrapped allocator upholds all of its guarantees, only the counters are
+// updated on the side.
+#[expect(unsafe_code, reason = "Implementing a global allocator is unsafe")]
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: The caller upholds the contract of `GlobalAlloc::alloc`.
+        let ptr = unsafe { self.allocator.alloc(layout) };
+
+        if !ptr.is_null() {
+            count(layout.size(), 0);
+        }
+
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: The caller upholds the contract of `GlobalAlloc::alloc_zeroed`.
+        let ptr = unsafe { self.allocator.alloc_zeroed(layout) };
+
+        if !ptr.is_null() {
+            count(layout.size(), 0);
+        }
+
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: The caller upholds the contract of `GlobalAlloc::dealloc`.
+        unsafe { self.allocator.dealloc(ptr, layout) };
+
+        count(0, layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // SAFETY: The caller upholds the contract of `GlobalAlloc::realloc`.
+        let new_ptr = unsafe { self.allocator.realloc(ptr, layout, new_size) };
+
+        if !new_ptr.is_null() {
+            count(new_size, layout.size());
+        }
+
+        new_ptr
+    }
+}
+
+/// Returns the number of bytes allocated by the current thread, minus the bytes it deallocated,
+/// as counted by the [`CountingAllocator`].
+#[must_use]
+pub fn allocated_bytes() -> isize {
+    ALLOCATED.with(Cell::get)
+}
+
+/// Calls `build` and returns the value it built, together with the number of bytes which
+/// remain allocated by the current thread afterwards.
+///
+/// Temporary allocations freed inside `build` are not accounted for.
+///
+/// # Panics
+/// If the [`CountingAllocator`] is not installed as the global allocator.
+pub fn measure_allocations<T>(build: impl FnOnce() -> T) -> (T, usize) {
+    let before = allocated_bytes();
+    let value = build();
+    let after = allocated_bytes();
+
+    assert!(
+        INSTALLED.load(Ordering::Relaxed),
+        "The CountingAllocator must be installed as the global allocator"
+    );
+
+    let allocated = usize::try_from(after - before)
+        .expect("Building the value freed more bytes than it allocated");
+
+    (value, allocated)
+}
+
+/// Builds a value by calling `build` and asserts that its [`GetSize::get_heap_size`] equals the
+/// number of bytes which remain allocated afterwards. Returns the value on success.
+///
+/// The value must only own the allocations made inside `build`, so it must not share them with
+/// values created beforehand.
+///
+/// # Panics
+/// If the heap size does not match the allocated bytes, or if the [`CountingAllocator`] is not
+/// installed as the global allocator.
+#[track_caller]
+pub fn assert_heap_size_matches<T: GetSize>(build: impl FnOnce() -> T) -> T {
+    let (value, allocated) = measure_allocations(build);
+
+    assert_eq!(
+        value.get_heap_size(),
+        allocated,
+        "The heap size of `{}` does not match the allocated bytes",
+        std::any::type_name::<T>(),
+    );
+
+    value
+}
@@ -1,6 +1,38 @@
+use std::alloc::Layout;
 use std::any::Any;
-use std::collections::BTreeMap;
-use std::sync::{Arc, Mutex, RwLock};
+use std::collections::{BTreeMap, BTreeSet};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+use crate::{Allocation, AllocatorModel, BTreeOccupancy, GetSize, LockPolicy, TrackingMode};
+
+/// The settings of a [`GetSizeTracker`], which determine how values are measured.
+///
+/// Further settings may be added in the future, so start from [`TrackerOptions::default`] and
+/// change the fields which matter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct TrackerOptions {
+    /// Which kind of reference to a shared ownership object is passed to
+    /// [`track`](GetSizeTracker::track). Defaults to [`TrackingMode::Strong`].
+    pub tracking_mode: TrackingMode,
+    /// Whether weak shared ownership objects, whose strong references have all been dropped,
+    /// account for the allocation they keep alive. Defaults to `false`, treating weak references
+    /// as not owning anything.
+    ///
+    /// Orphaned allocations are tracked by their address only, so the tracker does not keep them
+    /// alive. An allocation is only recognized as already measured while the measured weak
+    /// references are alive.
+    pub count_orphaned_weak: bool,
+    /// How densely the nodes of a [`BTreeMap`] or [`BTreeSet`] are assumed to be filled. Defaults
+    /// to [`BTreeOccupancy::Full`].
+    ///
+    /// [`BTreeSet`]: std::collections::BTreeSet
+    pub btree_occupancy: BTreeOccupancy,
+    /// Whether the value inside a [`Mutex`] or [`RwLock`] is waited for or skipped while the lock
+    /// is held elsewhere. Defaults to [`LockPolicy::Block`].
+    pub lock_policy: LockPolicy,
+}
 
 /// A tracker which makes sure that shared ownership objects are only accounted for once.
 pub trait GetSizeTracker {
@@ -12,58 +44,345 @@ pub trait GetSizeTracker {
     ///
     /// If the `addr` has not yet been seen, the tracker __MUST__ store the `strong_ref`
     /// object to ensure that the `addr` pointed to by it remains valid for the trackers
-    /// lifetime.
+    /// lifetime. Which kind of reference is passed is chosen by the
+    /// [`tracking_mode`](TrackerOptions::tracking_mode) of the tracker's options.
     fn track<A: Any + 'static, B>(&mut self, addr: *const B, strong_ref: A) -> bool;
+
+    /// Determines the settings used while measuring values with this tracker.
+    ///
+    /// The default implementation returns [`TrackerOptions::default`].
+    fn options(&self) -> TrackerOptions {
+        TrackerOptions::default()
+    }
+
+    /// Determines how many bytes the allocator reserves for a heap allocation with the given
+    /// non-zero sized `layout`.
+    ///
+    /// The default implementation returns the size of the `layout`, see
+    /// [`AllocatorModelTracker`] for rounding it up to the size classes of an allocator.
+    fn allocation_size(&self, layout: Layout) -> usize {
+        layout.size()
+    }
+
+    /// Determines how many bytes the allocator reserves for the given live `allocation`.
+    ///
+    /// This is used instead of [`allocation_size`](GetSizeTracker::allocation_size) for the
+    /// buffers of [`Vec`] and [`String`] as well as for [`Box`], whose pointers are known. The
+    /// default implementation only uses the layout of the `allocation`.
+    fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+        self.allocation_size(allocation.layout())
+    }
+
+    /// Tracks a value which has been skipped, since its lock was held elsewhere while using
+    /// [`LockPolicy::Skip`], or since its [`RefCell`](std::cell::RefCell) was mutably borrowed.
+    ///
+    /// The default implementation does nothing.
+    fn track_skipped_lock(&mut self) {}
+
+    /// Tracks that everything reachable through the shared ownership object located at `addr`
+    /// has been measured. It is called for every `addr` for which [`track`](GetSizeTracker::track)
+    /// returned `true`, once the measurement of the object is done.
+    ///
+    /// Until then, seeing the `addr` again means that it has been reached through a cycle. The
+    /// default implementation does nothing.
+    fn track_finished<B>(&mut self, addr: *const B) {
+        let _ = addr;
+    }
 }
 
-impl<T: GetSizeTracker> GetSizeTracker for &mut T {
+impl<T: GetSizeTracker + ?Sized> GetSizeTracker for &mut T {
     fn track<A: Any + 'static, B>(&mut self, addr: *const B, strong_ref: A) -> bool {
         GetSizeTracker::track(*self, addr, strong_ref)
     }
+
+    fn options(&self) -> TrackerOptions {
+        GetSizeTracker::options(&**self)
+    }
+
+    fn allocation_size(&self, layout: Layout) -> usize {
+        GetSizeTracker::allocation_size(&**self, layout)
+    }
+
+    fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+        GetSizeTracker::allocation_size_of(&**self, allocation)
+    }
+
+    fn track_skipped_lock(&mut self) {
+        GetSizeTracker::track_skipped_lock(*self);
+    }
+
+    fn track_finished<B>(&mut self, addr: *const B) {
+        GetSizeTracker::track_finished(*self, addr);
+    }
 }
 
 impl<T: GetSizeTracker> GetSizeTracker for Box<T> {
     fn track<A: Any + 'static, B>(&mut self, addr: *const B, strong_ref: A) -> bool {
         GetSizeTracker::track(&mut **self, addr, strong_ref)
     }
+
+    fn options(&self) -> TrackerOptions {
+        GetSizeTracker::options(&**self)
+    }
+
+    fn allocation_size(&self, layout: Layout) -> usize {
+        GetSizeTracker::allocation_size(&**self, layout)
+    }
+
+    fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+        GetSizeTracker::allocation_size_of(&**self, allocation)
+    }
+
+    fn track_skipped_lock(&mut self) {
+        GetSizeTracker::track_skipped_lock(&mut **self);
+    }
+
+    fn track_finished<B>(&mut self, addr: *const B) {
+        GetSizeTracker::track_finished(&mut **self, addr);
+    }
 }
 
 impl<T: GetSizeTracker> GetSizeTracker for Mutex<T> {
     fn track<A: Any + 'static, B>(&mut self, addr: *const B, strong_ref: A) -> bool {
-        let tracker = self.get_mut().expect("Mutex was poisoned");
+        let tracker = self.get_mut().unwrap_or_else(PoisonError::into_inner);
 
         GetSizeTracker::track(&mut *tracker, addr, strong_ref)
     }
+
+    fn options(&self) -> TrackerOptions {
+        let tracker = self.lock().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::options(&*tracker)
+    }
+
+    fn allocation_size(&self, layout: Layout) -> usize {
+        let tracker = self.lock().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::allocation_size(&*tracker, layout)
+    }
+
+    fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+        let tracker = self.lock().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::allocation_size_of(&*tracker, allocation)
+    }
+
+    fn track_skipped_lock(&mut self) {
+        let tracker = self.get_mut().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::track_skipped_lock(&mut *tracker);
+    }
+
+    fn track_finished<B>(&mut self, addr: *const B) {
+        let tracker = self.get_mut().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::track_finished(&mut *tracker, addr);
+    }
 }
 
 impl<T: GetSizeTracker> GetSizeTracker for RwLock<T> {
     fn track<A: Any + 'static, B>(&mut self, addr: *const B, strong_ref: A) -> bool {
-        let mut tracker = self.write().expect("RwLock was poisoned");
+        let mut tracker = self.write().unwrap_or_else(PoisonError::into_inner);
 
         GetSizeTracker::track(&mut *tracker, addr, strong_ref)
     }
+
+    fn options(&self) -> TrackerOptions {
+        let tracker = self.read().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::options(&*tracker)
+    }
+
+    fn allocation_size(&self, layout: Layout) -> usize {
+        let tracker = self.read().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::allocation_size(&*tracker, layout)
+    }
+
+    fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+        let tracker = self.read().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::allocation_size_of(&*tracker, allocation)
+    }
+
+    fn track_skipped_lock(&mut self) {
+        let tracker = self.get_mut().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::track_skipped_lock(&mut *tracker);
+    }
+
+    fn track_finished<B>(&mut self, addr: *const B) {
+        let tracker = self.get_mut().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::track_finished(&mut *tracker, addr);
+    }
 }
 
 impl<T: GetSizeTracker> GetSizeTracker for Arc<Mutex<T>> {
     fn track<A: Any + 'static, B>(&mut self, addr: *const B, strong_ref: A) -> bool {
-        let mut tracker = self.lock().expect("Mutex was poisoned");
+        let mut tracker = self.lock().unwrap_or_else(PoisonError::into_inner);
 
         GetSizeTracker::track(&mut *tracker, addr, strong_ref)
     }
+
+    fn options(&self) -> TrackerOptions {
+        let tracker = self.lock().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::options(&*tracker)
+    }
+
+    fn allocation_size(&self, layout: Layout) -> usize {
+        let tracker = self.lock().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::allocation_size(&*tracker, layout)
+    }
+
+    fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+        let tracker = self.lock().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::allocation_size_of(&*tracker, allocation)
+    }
+
+    fn track_skipped_lock(&mut self) {
+        let mut tracker = self.lock().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::track_skipped_lock(&mut *tracker);
+    }
+
+    fn track_finished<B>(&mut self, addr: *const B) {
+        let mut tracker = self.lock().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::track_finished(&mut *tracker, addr);
+    }
 }
 
 impl<T: GetSizeTracker> GetSizeTracker for Arc<RwLock<T>> {
     fn track<A: Any + 'static, B>(&mut self, addr: *const B, strong_ref: A) -> bool {
-        let mut tracker = self.write().expect("RwLock was poisoned");
+        let mut tracker = self.write().unwrap_or_else(PoisonError::into_inner);
 
         GetSizeTracker::track(&mut *tracker, addr, strong_ref)
     }
+
+    fn options(&self) -> TrackerOptions {
+        let tracker = self.read().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::options(&*tracker)
+    }
+
+    fn allocation_size(&self, layout: Layout) -> usize {
+        let tracker = self.read().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::allocation_size(&*tracker, layout)
+    }
+
+    fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+        let tracker = self.read().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::allocation_size_of(&*tracker, allocation)
+    }
+
+    fn track_skipped_lock(&mut self) {
+        let mut tracker = self.write().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::track_skipped_lock(&mut *tracker);
+    }
+
+    fn track_finished<B>(&mut self, addr: *const B) {
+        let mut tracker = self.write().unwrap_or_else(PoisonError::into_inner);
+
+        GetSizeTracker::track_finished(&mut *tracker, addr);
+    }
+}
+
+/// An object safe version of [`GetSizeTracker`], which allows trackers to be passed to
+/// [`DynGetSize`](crate::DynGetSize) implementations.
+///
+/// It is implemented for every [`GetSizeTracker`], and `dyn DynGetSizeTracker` does in turn
+/// implement [`GetSizeTracker`], so there is normally no need to implement it yourself.
+pub trait DynGetSizeTracker {
+    /// Tracks a type-erased strong shared ownership object `strong_ref`, which points to
+    /// an arbitrary object located at `addr`.
+    ///
+    /// See [`GetSizeTracker::track`] for the exact semantics.
+    fn track_dyn(&mut self, addr: *const (), strong_ref: Box<dyn Any>) -> bool;
+
+    /// See [`GetSizeTracker::options`].
+    fn options_dyn(&self) -> TrackerOptions;
+
+    /// See [`GetSizeTracker::allocation_size`].
+    fn allocation_size_dyn(&self, layout: Layout) -> usize;
+
+    /// See [`GetSizeTracker::allocation_size_of`].
+    fn allocation_size_of_dyn(&self, allocation: Allocation<'_>) -> usize;
+
+    /// See [`GetSizeTracker::track_skipped_lock`].
+    fn track_skipped_lock_dyn(&mut self);
+
+    /// See [`GetSizeTracker::track_finished`].
+    fn track_finished_dyn(&mut self, addr: *const ());
+}
+
+impl<T: GetSizeTracker> DynGetSizeTracker for T {
+    fn track_dyn(&mut self, addr: *const (), strong_ref: Box<dyn Any>) -> bool {
+        GetSizeTracker::track(self, addr, strong_ref)
+    }
+
+    fn options_dyn(&self) -> TrackerOptions {
+        GetSizeTracker::options(self)
+    }
+
+    fn allocation_size_dyn(&self, layout: Layout) -> usize {
+        GetSizeTracker::allocation_size(self, layout)
+    }
+
+    fn allocation_size_of_dyn(&self, allocation: Allocation<'_>) -> usize {
+        GetSizeTracker::allocation_size_of(self, allocation)
+    }
+
+    fn track_skipped_lock_dyn(&mut self) {
+        GetSizeTracker::track_skipped_lock(self);
+    }
+
+    fn track_finished_dyn(&mut self, addr: *const ()) {
+        GetSizeTracker::track_finished(self, addr);
+    }
+}
+
+impl GetSizeTracker for dyn DynGetSizeTracker + '_ {
+    fn track<A: Any + 'static, B>(&mut self, addr: *const B, strong_ref: A) -> bool {
+        DynGetSizeTracker::track_dyn(self, addr.cast::<()>(), Box::new(strong_ref))
+    }
+
+    fn options(&self) -> TrackerOptions {
+        DynGetSizeTracker::options_dyn(self)
+    }
+
+    fn allocation_size(&self, layout: Layout) -> usize {
+        DynGetSizeTracker::allocation_size_dyn(self, layout)
+    }
+
+    fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+        DynGetSizeTracker::allocation_size_of_dyn(self, allocation)
+    }
+
+    fn track_skipped_lock(&mut self) {
+        DynGetSizeTracker::track_skipped_lock_dyn(self);
+    }
+
+    fn track_finished<B>(&mut self, addr: *const B) {
+        DynGetSizeTracker::track_finished_dyn(self, addr.cast::<()>());
+    }
 }
 
 /// A simple standard tracker which can be used to track shared ownership references.
 #[derive(Debug, Default)]
 pub struct StandardTracker {
     inner: BTreeMap<usize, Box<dyn Any + 'static>>,
+    options: TrackerOptions,
+    skipped_locks: usize,
+    detect_cycles: bool,
+    pending: BTreeSet<usize>,
+    cycles: usize,
 }
 
 impl StandardTracker {
@@ -72,8 +391,58 @@ impl StandardTracker {
         Self::default()
     }
 
+    /// Forgets all tracked objects and resets the counters of skipped locks and cycles.
     pub fn clear(&mut self) {
         self.inner.clear();
+        self.pending.clear();
+        self.skipped_locks = 0;
+        self.cycles = 0;
+    }
+
+    /// Changes whether weak references account for the allocation they keep alive once all
+    /// strong references have been dropped. See [`TrackerOptions::count_orphaned_weak`].
+    pub fn set_count_orphaned_weak(&mut self, count_orphaned_weak: bool) {
+        self.options.count_orphaned_weak = count_orphaned_weak;
+    }
+
+    /// Changes how densely the nodes of a [`BTreeMap`] or [`BTreeSet`] are assumed to be filled.
+    /// See [`TrackerOptions::btree_occupancy`].
+    ///
+    /// [`BTreeSet`]: std::collections::BTreeSet
+    pub fn set_btree_occupancy(&mut self, btree_occupancy: BTreeOccupancy) {
+        self.options.btree_occupancy = btree_occupancy;
+    }
+
+    /// Changes whether locked values are waited for or skipped. See
+    /// [`TrackerOptions::lock_policy`].
+    pub fn set_lock_policy(&mut self, lock_policy: LockPolicy) {
+        self.options.lock_policy = lock_policy;
+    }
+
+    /// Get the number of values which have been skipped, since their locks were held elsewhere
+    /// or their [`RefCell`](std::cell::RefCell)s were mutably borrowed.
+    #[must_use]
+    pub const fn skipped_locks(&self) -> usize {
+        self.skipped_locks
+    }
+
+    /// Changes whether cycles between shared ownership objects are detected. They are counted
+    /// by [`cycles`](Self::cycles).
+    ///
+    /// Cycles never prevent a measurement from terminating, since every shared ownership object
+    /// is only measured once. Detecting them requires remembering which objects are still being
+    /// measured, so it is disabled by default.
+    pub fn set_detect_cycles(&mut self, detect_cycles: bool) {
+        self.detect_cycles = detect_cycles;
+    }
+
+    /// Get the number of times a shared ownership object has been reached again while it was
+    /// still being measured, which means that it is part of a cycle.
+    ///
+    /// This is only counted while [`set_detect_cycles`](Self::set_detect_cycles) is enabled.
+    #[must_use]
+    pub const fn cycles(&self) -> usize {
+        self.cycles
     }
 }
 
@@ -86,11 +455,217 @@ impl GetSizeTracker for StandardTracker {
 
             e.insert(strong_ref);
 
+            if self.detect_cycles {
+                self.pending.insert(addr);
+            }
+
             true
         } else {
+            if self.pending.contains(&addr) {
+                self.cycles += 1;
+            }
+
             false
         }
     }
+
+    fn options(&self) -> TrackerOptions {
+        self.options
+    }
+
+    fn track_skipped_lock(&mut self) {
+        self.skipped_locks += 1;
+    }
+
+    fn track_finished<B>(&mut self, addr: *const B) {
+        self.pending.remove(&(addr as usize));
+    }
+}
+
+/// Implements [`GetSizeTracker`] for a wrapper around a [`StandardTracker`] stored in its
+/// `tracker` field, which passes the given [`TrackingMode`] to the tracked objects.
+macro_rules! impl_tracker_mode {
+    ($ty:ty, $mode:expr) => {
+        impl GetSizeTracker for $ty {
+            fn track<A: Any + 'static, B>(&mut self, addr: *const B, strong_ref: A) -> bool {
+                GetSizeTracker::track(&mut self.tracker, addr, strong_ref)
+            }
+
+            fn options(&self) -> TrackerOptions {
+                TrackerOptions {
+                    tracking_mode: $mode,
+                    ..GetSizeTracker::options(&self.tracker)
+                }
+            }
+
+            fn allocation_size(&self, layout: Layout) -> usize {
+                GetSizeTracker::allocation_size(&self.tracker, layout)
+            }
+
+            fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+                GetSizeTracker::allocation_size_of(&self.tracker, allocation)
+            }
+
+            fn track_skipped_lock(&mut self) {
+                GetSizeTracker::track_skipped_lock(&mut self.tracker);
+            }
+
+            fn track_finished<B>(&mut self, addr: *const B) {
+                GetSizeTracker::track_finished(&mut self.tracker, addr);
+            }
+        }
+    };
+}
+
+/// A tracker which only records the addresses of shared ownership objects, without keeping them
+/// alive. See [`TrackingMode::Address`].
+///
+/// The tracker borrows the measured root value for its whole lifetime, so the objects reachable
+/// from it can not be dropped while it is in use. This avoids updating the reference counter of
+/// every tracked object, as well as delaying their deallocation.
+///
+/// Values behind locks may however still be changed by other threads once they have been
+/// measured. If such a value is dropped and its memory reused by another shared object, that one
+/// is mistaken as already counted. Use a [`StandardTracker`] if this matters.
+///
+/// Use [`ScopedTracker::measure`] in order to measure the root value with a tracker borrowing it.
+/// A tracker created by [`ScopedTracker::new`] must only be used for measuring the given root.
+#[derive(Debug)]
+pub struct ScopedTracker<'a> {
+    tracker: StandardTracker,
+    root: PhantomData<&'a ()>,
+}
+
+impl<'a> ScopedTracker<'a> {
+    /// Creates a new tracker for measuring the given `root` value, which stays borrowed while the
+    /// tracker is alive.
+    #[must_use]
+    pub fn new<R: ?Sized>(root: &'a R) -> Self {
+        let _ = root;
+
+        Self {
+            tracker: StandardTracker::default(),
+            root: PhantomData,
+        }
+    }
+
+    /// Determines how many bytes the `root` value occupies inside the heap, using a tracker which
+    /// borrows it for the whole measurement.
+    ///
+    /// The tracker is returned as well, so its counters can be inspected afterwards.
+    pub fn measure<T: GetSize>(root: &'a T) -> (usize, Self) {
+        GetSize::get_heap_size_with_tracker(root, Self::new(root))
+    }
+
+    /// Get the wrapped tracker, which stores the addresses.
+    #[must_use]
+    pub const fn tracker(&self) -> &StandardTracker {
+        &self.tracker
+    }
+
+    /// Get the wrapped tracker mutably, in order to change its settings.
+    pub const fn tracker_mut(&mut self) -> &mut StandardTracker {
+        &mut self.tracker
+    }
+}
+
+impl_tracker_mode!(ScopedTracker<'_>, TrackingMode::Address);
+
+/// A tracker which stores weak references to the shared ownership objects it has seen. See
+/// [`TrackingMode::Weak`].
+///
+/// Unlike a [`StandardTracker`], it does not keep the tracked values alive, so it can be kept
+/// around for a long time. Only the memory of the allocations is kept until the tracker is
+/// cleared or dropped, which makes sure that their addresses are not reused in the meantime.
+#[derive(Debug, Default)]
+pub struct WeakTracker {
+    tracker: StandardTracker,
+}
+
+impl WeakTracker {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets all tracked objects, releasing the memory of those which have been dropped, and
+    /// resets the counters of the wrapped tracker.
+    pub fn clear(&mut self) {
+        self.tracker.clear();
+    }
+
+    /// Get the wrapped tracker, which stores the weak references.
+    #[must_use]
+    pub const fn tracker(&self) -> &StandardTracker {
+        &self.tracker
+    }
+
+    /// Get the wrapped tracker mutably, in order to change its settings.
+    pub const fn tracker_mut(&mut self) -> &mut StandardTracker {
+        &mut self.tracker
+    }
+}
+
+impl_tracker_mode!(WeakTracker, TrackingMode::Weak);
+
+/// A tracker which rounds every heap allocation up to the size classes of an [`AllocatorModel`],
+/// while leaving everything else to the wrapped tracker.
+#[derive(Debug, Clone, Default)]
+pub struct AllocatorModelTracker<M, T = StandardTracker> {
+    model: M,
+    tracker: T,
+}
+
+impl<M, T> AllocatorModelTracker<M, T> {
+    /// Creates a new tracker, which rounds the allocations according to the given `model` and
+    /// forwards everything else to the given `tracker`.
+    #[must_use]
+    pub const fn new(model: M, tracker: T) -> Self {
+        Self { model, tracker }
+    }
+
+    /// Get the allocator model used by this tracker.
+    #[must_use]
+    pub const fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// Get the wrapped tracker.
+    #[must_use]
+    pub const fn tracker(&self) -> &T {
+        &self.tracker
+    }
+
+    /// Consumes this tracker, returning the wrapped tracker.
+    pub fn into_inner(self) -> T {
+        self.tracker
+    }
+}
+
+impl<M: AllocatorModel, T: GetSizeTracker> GetSizeTracker for AllocatorModelTracker<M, T> {
+    fn track<A: Any + 'static, B>(&mut self, addr: *const B, strong_ref: A) -> bool {
+        GetSizeTracker::track(&mut self.tracker, addr, strong_ref)
+    }
+
+    fn options(&self) -> TrackerOptions {
+        GetSizeTracker::options(&self.tracker)
+    }
+
+    fn allocation_size(&self, layout: Layout) -> usize {
+        AllocatorModel::allocation_size(&self.model, layout)
+    }
+
+    fn allocation_size_of(&self, allocation: Allocation<'_>) -> usize {
+        AllocatorModel::allocation_size_of(&self.model, allocation)
+    }
+
+    fn track_skipped_lock(&mut self) {
+        GetSizeTracker::track_skipped_lock(&mut self.tracker);
+    }
+
+    fn track_finished<B>(&mut self, addr: *const B) {
+        GetSizeTracker::track_finished(&mut self.tracker, addr);
+    }
 }
 
 /// A pseudo tracker which does not track anything.